@@ -3,11 +3,139 @@
 extern crate test;
 extern crate rust_wasm_game_of_life;
 
+use rust_wasm_game_of_life::Universe;
+
+fn random_universe(width: u32, height: u32) -> Universe {
+    let mut universe = Universe::new();
+    universe.set_topology(&format!("T{},{}", width, height)).unwrap();
+    universe.reset().unwrap();
+    universe
+}
+
 #[bench]
 fn universe_ticks(b: &mut test::Bencher) {
-    let mut universe = rust_wasm_game_of_life::Universe::new();
+    let mut universe = Universe::new();
+
+    b.iter(|| {
+        universe.tick().unwrap();
+    });
+}
+
+#[bench]
+fn universe_ticks_scalar(b: &mut test::Bencher) {
+    let mut universe = Universe::new();
+
+    b.iter(|| {
+        universe.tick_scalar();
+    });
+}
+
+#[bench]
+fn large_universe_ticks(b: &mut test::Bencher) {
+    let mut universe = random_universe(512, 512);
+
+    b.iter(|| {
+        universe.tick().unwrap();
+    });
+}
+
+#[bench]
+fn large_universe_ticks_scalar(b: &mut test::Bencher) {
+    let mut universe = random_universe(512, 512);
+
+    b.iter(|| {
+        universe.tick_scalar();
+    });
+}
+
+#[bench]
+fn large_universe_ticks_lookup(b: &mut test::Bencher) {
+    let mut universe = random_universe(512, 512);
+
+    b.iter(|| {
+        universe.tick_lookup();
+    });
+}
+
+#[bench]
+fn large_isotropic_universe_ticks_lookup(b: &mut test::Bencher) {
+    let mut universe = random_universe(512, 512);
+    universe.set_rule("B2-a/S12").unwrap();
 
     b.iter(|| {
-        universe.tick();
+        universe.tick_lookup();
     });
-}
\ No newline at end of file
+}
+
+#[bench]
+fn large_isotropic_universe_ticks_scalar(b: &mut test::Bencher) {
+    let mut universe = random_universe(512, 512);
+    universe.set_rule("B2-a/S12").unwrap();
+
+    b.iter(|| {
+        universe.tick_scalar();
+    });
+}
+
+// A random universe after it has mostly settled into ash.
+fn settled_universe(width: u32, height: u32) -> Universe {
+    let mut universe = random_universe(width, height);
+    for _ in 0..1000 {
+        universe.tick().unwrap();
+    }
+    universe
+}
+
+#[bench]
+fn large_settled_universe_ticks(b: &mut test::Bencher) {
+    let mut universe = settled_universe(512, 512);
+
+    b.iter(|| {
+        universe.tick().unwrap();
+    });
+}
+
+#[bench]
+fn large_settled_universe_ticks_active(b: &mut test::Bencher) {
+    let mut universe = settled_universe(512, 512);
+
+    b.iter(|| {
+        universe.tick_active();
+    });
+}
+
+#[cfg(feature = "parallel")]
+fn parallel_ticks(b: &mut test::Bencher, threads: usize) {
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+    let mut universe = random_universe(2048, 2048);
+
+    pool.install(|| {
+        b.iter(|| {
+            universe.tick_in_bands(threads);
+        });
+    });
+}
+
+#[cfg(feature = "parallel")]
+#[bench]
+fn parallel_ticks_1_thread(b: &mut test::Bencher) {
+    parallel_ticks(b, 1);
+}
+
+#[cfg(feature = "parallel")]
+#[bench]
+fn parallel_ticks_2_threads(b: &mut test::Bencher) {
+    parallel_ticks(b, 2);
+}
+
+#[cfg(feature = "parallel")]
+#[bench]
+fn parallel_ticks_4_threads(b: &mut test::Bencher) {
+    parallel_ticks(b, 4);
+}
+
+#[cfg(feature = "parallel")]
+#[bench]
+fn parallel_ticks_8_threads(b: &mut test::Bencher) {
+    parallel_ticks(b, 8);
+}
@@ -0,0 +1,60 @@
+//! The ways a dense universe can compute its next generation.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::Error;
+
+/// How `Universe::tick` computes the next generation. Every algorithm gives
+/// the same generations, but each is only faster for some rules: a rule an
+/// algorithm does not support is computed cell by cell instead.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Algorithm {
+    /// The fastest algorithm supporting the rule.
+    #[default]
+    Auto,
+    /// Cell by cell, supporting every rule.
+    Scalar,
+    /// 64 or 128 cells at a time with adder logic, for totalistic rules on
+    /// the 8 immediate neighbours, or the 6 of a hexagonal grid, with two
+    /// states and without B0.
+    Bitwise,
+    /// 2×2 tiles at a time, looking up the next generation of every 4×4
+    /// block in a table, for rules on the 8 immediate neighbours, or the 6
+    /// of a hexagonal grid, with two states.
+    LookupTable,
+    /// Like `LookupTable`, but only in the tiles of 32×32 cells that changed
+    /// in the last generation and the tiles around them. The others are
+    /// stable and carried over.
+    ActiveTiles,
+}
+
+impl FromStr for Algorithm {
+    type Err = Error;
+
+    /// Parse the name of an algorithm: `auto`, `scalar`, `bitwise`,
+    /// `lookup` or `active`.
+    fn from_str(s: &str) -> Result<Algorithm, Error> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "auto" => Ok(Algorithm::Auto),
+            "scalar" => Ok(Algorithm::Scalar),
+            "bitwise" => Ok(Algorithm::Bitwise),
+            "lookup" => Ok(Algorithm::LookupTable),
+            "active" => Ok(Algorithm::ActiveTiles),
+            _ => Err(Error::ParseError(format!("unknown algorithm '{}', expected auto, scalar, bitwise, lookup or active", s))),
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Algorithm::Auto => "auto",
+            Algorithm::Scalar => "scalar",
+            Algorithm::Bitwise => "bitwise",
+            Algorithm::LookupTable => "lookup",
+            Algorithm::ActiveTiles => "active",
+        };
+        write!(f, "{}", name)
+    }
+}
@@ -0,0 +1,86 @@
+//! Where the cells of a universe stay when it is resized.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::Error;
+
+/// The point of a universe that stays in place when it is resized, see
+/// `Universe::resize`: rows and columns are added or removed on the other
+/// sides of it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// Get how many rows and columns the cells move by when a `width` ×
+    /// `height` universe is resized to `new_width` × `new_height`. Around
+    /// the centre, an odd number of rows or columns added or removed puts
+    /// the extra one at the bottom or right.
+    pub fn offset(self, width: u32, height: u32, new_width: u32, new_height: u32) -> (i64, i64) {
+        let (rows, columns) = (new_height as i64 - height as i64, new_width as i64 - width as i64);
+        let (vertical, horizontal) = match self {
+            Anchor::TopLeft => (0, 0),
+            Anchor::Top => (0, 1),
+            Anchor::TopRight => (0, 2),
+            Anchor::Left => (1, 0),
+            Anchor::Center => (1, 1),
+            Anchor::Right => (1, 2),
+            Anchor::BottomLeft => (2, 0),
+            Anchor::Bottom => (2, 1),
+            Anchor::BottomRight => (2, 2),
+        };
+        (rows * vertical / 2, columns * horizontal / 2)
+    }
+}
+
+impl FromStr for Anchor {
+    type Err = Error;
+
+    /// Parse the name of an anchor: `top-left`, `top`, `top-right`, `left`,
+    /// `center`, `right`, `bottom-left`, `bottom` or `bottom-right`.
+    fn from_str(s: &str) -> Result<Anchor, Error> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "top-left" => Ok(Anchor::TopLeft),
+            "top" => Ok(Anchor::Top),
+            "top-right" => Ok(Anchor::TopRight),
+            "left" => Ok(Anchor::Left),
+            "center" | "centre" => Ok(Anchor::Center),
+            "right" => Ok(Anchor::Right),
+            "bottom-left" => Ok(Anchor::BottomLeft),
+            "bottom" => Ok(Anchor::Bottom),
+            "bottom-right" => Ok(Anchor::BottomRight),
+            _ => Err(Error::ParseError(format!(
+                "unknown anchor '{}', expected top-left, top, top-right, left, center, right, bottom-left, bottom or bottom-right",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Anchor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Anchor::TopLeft => "top-left",
+            Anchor::Top => "top",
+            Anchor::TopRight => "top-right",
+            Anchor::Left => "left",
+            Anchor::Center => "center",
+            Anchor::Right => "right",
+            Anchor::BottomLeft => "bottom-left",
+            Anchor::Bottom => "bottom",
+            Anchor::BottomRight => "bottom-right",
+        };
+        write!(f, "{}", name)
+    }
+}
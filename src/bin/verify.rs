@@ -0,0 +1,163 @@
+//! Check that two engines compute the same generations of random soups.
+//!
+//! ```text
+//! cargo run --bin verify -- [OPTIONS] REFERENCE CANDIDATE
+//! ```
+//!
+//! An engine is either an algorithm of a dense universe, `auto`, `scalar`,
+//! `bitwise`, `lookup` or `active`, or an unbounded backend, `sparse` or
+//! `hashlife`. Unbounded backends only agree with a dense universe until
+//! a pattern reaches its edges.
+
+extern crate rust_wasm_game_of_life;
+
+use std::env;
+use std::error::Error;
+use std::process;
+
+use rust_wasm_game_of_life::platform;
+use rust_wasm_game_of_life::{
+    Algorithm, Engine, Hashlife, Lockstep, Rule, Soup, SparseUniverse, Symmetry, Topology, Universe,
+};
+
+const USAGE: &str = "usage: verify [OPTIONS] REFERENCE CANDIDATE
+
+Runs two engines in lockstep on random soups and reports the first
+generation and cell they disagree on.
+
+engines:
+    auto, scalar, bitwise, lookup, active   a dense universe with that algorithm
+    sparse, hashlife                        an unbounded universe
+
+options:
+    --rule RULE           the rule of the soups [default: B3/S23]
+    --topology SPEC       the size and edges of dense universes [default: T128,128]
+    --density DENSITY     the fraction of live cells in a soup [default: 0.5]
+    --symmetry SYMMETRY   the symmetry of the soups, C1, C2, C4, D2, D4 or D8 [default: C1]
+    --seed SEED           the seed of the first soup [default: 1]
+    --soups COUNT         the number of soups [default: 10]
+    --generations COUNT   the generations to run each soup for [default: 1000]
+    --timers              log the time taken by every generation";
+
+struct Options {
+    rule: Rule,
+    topology: String,
+    density: f64,
+    symmetry: Symmetry,
+    seed: u64,
+    soups: u64,
+    generations: u64,
+    engines: Vec<String>,
+}
+
+fn parse_options() -> Result<Options, Box<dyn Error>> {
+    let mut options = Options {
+        rule: Rule::default(),
+        topology: "T128,128".to_string(),
+        density: 0.5,
+        symmetry: Symmetry::C1,
+        seed: 1,
+        soups: 10,
+        generations: 1000,
+        engines: Vec::new(),
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            options.engines.push(arg);
+            continue;
+        }
+        if arg == "--timers" {
+            platform::set_timers(true);
+            continue;
+        }
+
+        let value = args.next().ok_or(format!("{} needs a value", arg))?;
+        let number = |value: &str| value.parse::<u64>().map_err(|e| format!("{} {}: {}", arg, value, e));
+        match arg.as_str() {
+            "--rule" => options.rule = value.parse()?,
+            "--topology" => options.topology = value,
+            "--density" => {
+                options.density = value.parse().map_err(|e| format!("{} {}: {}", arg, value, e))?;
+            }
+            "--symmetry" => options.symmetry = value.parse()?,
+            "--seed" => options.seed = number(&value)?,
+            "--soups" => options.soups = number(&value)?,
+            "--generations" => options.generations = number(&value)?,
+            _ => return Err(format!("unknown option {}", arg).into()),
+        }
+    }
+
+    if options.engines.len() != 2 {
+        return Err("expected a reference and a candidate engine".into());
+    }
+    Topology::parse(&options.topology)?;
+    Ok(options)
+}
+
+/// Create an empty engine from its name.
+fn engine(name: &str, options: &Options) -> Result<Box<dyn Engine>, Box<dyn Error>> {
+    let mut engine: Box<dyn Engine> = match name {
+        "sparse" => Box::new(SparseUniverse::new()),
+        "hashlife" => Box::new(Hashlife::new()),
+        _ => {
+            let algorithm: Algorithm = name.parse()?;
+            let mut universe = Universe::new();
+            universe.set_topology(&options.topology)?;
+            universe.reset_all_dead();
+            universe.replace_algorithm(algorithm);
+            Box::new(universe)
+        }
+    };
+
+    engine.replace_rule(options.rule.clone())?;
+    Ok(engine)
+}
+
+fn run(options: &Options) -> Result<bool, Box<dyn Error>> {
+    let (_, width, height) = Topology::parse(&options.topology)?;
+
+    for seed in (0..options.soups).map(|i| options.seed.wrapping_add(i)) {
+        let mut reference = engine(&options.engines[0], options)?;
+        let mut candidate = engine(&options.engines[1], options)?;
+        let soup = Soup { seed, density: options.density, region: None, symmetry: options.symmetry };
+        for (row, column) in soup.cells(width, height)? {
+            reference.set(row as i64, column as i64, true)?;
+            candidate.set(row as i64, column as i64, true)?;
+        }
+
+        let divergence = match Lockstep::new(reference, candidate) {
+            Ok(mut lockstep) => {
+                let divergence = lockstep.run(options.generations)?;
+                if divergence.is_none() {
+                    println!("soup {}: agreed for {} generations", seed, lockstep.generation());
+                }
+                divergence
+            }
+            Err(divergence) => Some(divergence),
+        };
+        if let Some(divergence) = divergence {
+            println!("soup {}: {} and {} disagree in {}", seed, options.engines[0], options.engines[1], divergence);
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+fn main() {
+    let options = parse_options().unwrap_or_else(|error| {
+        eprintln!("{}\n\n{}", error, USAGE);
+        process::exit(2);
+    });
+
+    match run(&options) {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(2);
+        }
+    }
+}
@@ -0,0 +1,330 @@
+//! A generation step that computes a whole word of cells at once, with
+//! their neighbour counts summed bit by bit in adder logic.
+//!
+//! Each row of cells is read out of the bitset into 64-bit words, padded
+//! with the cell beyond either edge. Shifting a padded row by one bit
+//! either way lines every cell up with its west or east neighbour, so the
+//! 8 neighbours of a word of cells are 8 words, added into the 4 bit-planes
+//! of their counts. Words are 64 cells, or 128 with the `simd` feature.
+
+use std::ops::{BitAnd, BitOr, BitXor, Not, Range};
+
+use fixedbitset::FixedBitSet;
+
+use crate::rule::{Neighborhood, Rule};
+use crate::topology::Topology;
+
+#[cfg(feature = "simd")]
+type Lanes = crate::simd::Lanes;
+#[cfg(not(feature = "simd"))]
+type Lanes = u64;
+
+/// A word of cells, one per bit, that `tick` computes at once.
+pub trait Word:
+    Copy + BitAnd<Output = Self> + BitOr<Output = Self> + BitXor<Output = Self> + Not<Output = Self>
+{
+    /// The number of cells in a word, a multiple of 64.
+    const BITS: usize;
+
+    fn zero() -> Self;
+
+    /// Load the cells of a padded row from a bit position, see `extract`.
+    fn load(padded: &[u64], bit: usize) -> Self;
+
+    /// Store the cells into `BITS / 64` words, the first cells first.
+    fn store(self, words: &mut [u64]);
+}
+
+impl Word for u64 {
+    const BITS: usize = 64;
+
+    fn zero() -> u64 {
+        0
+    }
+
+    #[inline]
+    fn load(padded: &[u64], bit: usize) -> u64 {
+        extract(padded, bit)
+    }
+
+    #[inline]
+    fn store(self, words: &mut [u64]) {
+        words[0] = self;
+    }
+}
+
+/// Returns whether `tick` can advance a universe following a rule: a
+/// totalistic rule on the 8 immediate neighbours, or the 6 of a hexagonal
+/// grid, with two states and without B0.
+pub fn supports(rule: &Rule) -> bool {
+    rule.is_life_like()
+        && rule.is_totalistic()
+        && !rule.is_generations()
+        && !rule.has_b0()
+        && matches!(rule.neighborhood(), Neighborhood::Moore | Neighborhood::Hexagonal)
+}
+
+/// Read `len` bits, at most 64, from a bit position of the blocks of a
+/// bitset.
+pub(crate) fn read_bits(blocks: &[u32], start: usize, len: usize) -> u64 {
+    let (first, shift) = (start / 32, start % 32);
+    let mut bits = 0u128;
+    for (i, &block) in blocks[first..].iter().take((shift + len).div_ceil(32)).enumerate() {
+        bits |= (block as u128) << (32 * i);
+    }
+
+    let bits = (bits >> shift) as u64;
+    if len < 64 {
+        bits & ((1 << len) - 1)
+    } else {
+        bits
+    }
+}
+
+/// Write the lowest `len` bits, at most 64, to a bit position of the blocks
+/// of a bitset.
+pub(crate) fn write_bits(blocks: &mut [u32], start: usize, bits: u64, len: usize) {
+    for i in 0..len.div_ceil(32) {
+        let part_len = (len - 32 * i).min(32);
+        let mask = if part_len == 32 { !0 } else { (1 << part_len) - 1 };
+        let part = (bits >> (32 * i)) as u32 & mask;
+
+        let (block, shift) = ((start + 32 * i) / 32, (start + 32 * i) % 32);
+        blocks[block] = blocks[block] & !(mask << shift) | part << shift;
+        if shift + part_len > 32 {
+            let spill = 32 - shift;
+            blocks[block + 1] = blocks[block + 1] & !(mask >> spill) | part >> spill;
+        }
+    }
+}
+
+/// Read a row, which may lie beyond the top or bottom edge, into padded
+/// words: bit `c + 1` is the cell in column `c`, from -1 to `width`. Cells
+/// beyond the edges of a plane read as `outside`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn read_row(
+    cells: &FixedBitSet,
+    width: u32,
+    height: u32,
+    topology: Topology,
+    outside: bool,
+    row: i64,
+    padded: &mut [u64],
+) {
+    padded.iter_mut().for_each(|word| *word = 0);
+    let alive = |row: i64, column: i64| match topology.wrap(row, column, width, height) {
+        Some((r, c)) => cells[(r * width + c) as usize],
+        None => outside,
+    };
+
+    if (0..height as i64).contains(&row) {
+        let start = row as usize * width as usize;
+        for k in 0..(width as usize).div_ceil(64) {
+            let len = (width as usize - 64 * k).min(64);
+            let word = read_bits(cells.as_slice(), start + 64 * k, len);
+            padded[k] |= word << 1;
+            padded[k + 1] |= word >> 63;
+        }
+        padded[0] |= alive(row, -1) as u64;
+        padded[(width as usize + 1) / 64] |= (alive(row, width as i64) as u64) << ((width as usize + 1) % 64);
+    } else {
+        for column in -1..=width as i64 {
+            let bit = (column + 1) as usize;
+            padded[bit / 64] |= (alive(row, column) as u64) << (bit % 64);
+        }
+    }
+}
+
+/// The 64 bits of a padded row from a bit position.
+#[inline]
+pub fn extract(padded: &[u64], bit: usize) -> u64 {
+    let (word, shift) = (bit / 64, bit % 64);
+    if shift == 0 {
+        padded[word]
+    } else {
+        padded[word] >> shift | padded[word + 1] << (64 - shift)
+    }
+}
+
+/// Compute the next generation of a `width` × `height` universe into
+/// `next`, which must be as large as `cells`. The rows being computed are
+/// kept in `scratch`, which only allocates the first time.
+///
+/// # Panics
+///
+/// Panics if the rule is not supported, see `supports`.
+pub fn tick(
+    cells: &FixedBitSet,
+    width: u32,
+    height: u32,
+    topology: Topology,
+    rule: &Rule,
+    next: &mut FixedBitSet,
+    scratch: &mut Vec<u64>,
+) {
+    tick_with::<Lanes>(cells, width, height, topology, rule, next, scratch);
+}
+
+/// Compute the next generation like `tick`, a given word of cells at once.
+pub fn tick_with<W: Word>(
+    cells: &FixedBitSet,
+    width: u32,
+    height: u32,
+    topology: Topology,
+    rule: &Rule,
+    next: &mut FixedBitSet,
+    scratch: &mut Vec<u64>,
+) {
+    let universe = Bitwise::new(cells, width, height, topology, rule);
+    universe.tick_rows::<W>(0..height as usize, next.as_mut_slice(), scratch);
+}
+
+/// Compute the next generation like `tick`, splitting the rows into about
+/// `bands` bands computed in parallel on the rayon thread pool. Every band
+/// starts on a row that begins a block of the bitset, so that bands write
+/// to blocks of their own.
+#[cfg(feature = "parallel")]
+pub fn tick_in_bands(
+    cells: &FixedBitSet,
+    width: u32,
+    height: u32,
+    topology: Topology,
+    rule: &Rule,
+    next: &mut FixedBitSet,
+    bands: usize,
+) {
+    use rayon::prelude::*;
+
+    let universe = Bitwise::new(cells, width, height, topology, rule);
+    let (width, height) = (width as usize, height as usize);
+    let aligned = 32 / gcd(width, 32);
+    let band_rows = height.div_ceil(bands.max(1)).div_ceil(aligned) * aligned;
+
+    next.as_mut_slice()
+        .par_chunks_mut(band_rows * width / 32)
+        .enumerate()
+        .filter(|&(band, _)| band * band_rows < height)
+        .for_each(|(band, blocks)| {
+            let start = band * band_rows;
+            universe.tick_rows::<Lanes>(start..(start + band_rows).min(height), blocks, &mut Vec::new());
+        });
+}
+
+#[cfg(feature = "parallel")]
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// A universe and the rule it follows, checked to be supported.
+struct Bitwise<'a> {
+    cells: &'a FixedBitSet,
+    width: u32,
+    height: u32,
+    topology: Topology,
+    // Whether a dead cell is born and a live cell survives, for every
+    // count of live neighbours up to the size of the neighbourhood.
+    outcomes: [(bool, bool); 9],
+    hexagonal: bool,
+}
+
+impl<'a> Bitwise<'a> {
+    fn new(cells: &'a FixedBitSet, width: u32, height: u32, topology: Topology, rule: &Rule) -> Bitwise<'a> {
+        assert!(supports(rule), "rule '{}' cannot be computed bitwise", rule);
+
+        let mut outcomes = [(false, false); 9];
+        let size = rule.neighborhood().size(1).expect("bitwise rules have a standard neighbourhood");
+        for (n, outcome) in outcomes.iter_mut().enumerate().take(size as usize + 1) {
+            *outcome = (rule.next_state(false, n as u32), rule.next_state(true, n as u32));
+        }
+
+        Bitwise {
+            cells,
+            width,
+            height,
+            topology,
+            outcomes,
+            hexagonal: rule.neighborhood() == Neighborhood::Hexagonal,
+        }
+    }
+
+    /// Compute the next generation of a range of rows into blocks starting
+    /// with the first cell of the range.
+    fn tick_rows<W: Word>(&self, rows: Range<usize>, blocks: &mut [u32], scratch: &mut Vec<u64>) {
+        let (cells, width, height, topology) = (self.cells, self.width, self.height, self.topology);
+        let lanes = W::BITS / 64;
+        let words = (width as usize).div_ceil(W::BITS) * lanes + 1;
+
+        // The rows around the one being computed, and its next generation.
+        scratch.resize(3 * words + lanes, 0);
+        let (mut north, rest) = scratch.split_at_mut(words);
+        let (mut middle, rest) = rest.split_at_mut(words);
+        let (mut south, alive_words) = rest.split_at_mut(words);
+        read_row(cells, width, height, topology, false, rows.start as i64 - 1, north);
+        read_row(cells, width, height, topology, false, rows.start as i64, middle);
+
+        for row in rows.clone() {
+            read_row(cells, width, height, topology, false, row as i64 + 1, south);
+
+            for bit in (0..width as usize).step_by(W::BITS) {
+                let centre = W::load(middle, bit + 1);
+                let mut neighbors = [
+                    W::load(north, bit),
+                    W::load(north, bit + 1),
+                    W::load(north, bit + 2),
+                    W::load(middle, bit),
+                    W::load(middle, bit + 2),
+                    W::load(south, bit),
+                    W::load(south, bit + 1),
+                    W::load(south, bit + 2),
+                ];
+                // Hexagonal grids leave out the north-east and south-west.
+                if self.hexagonal {
+                    neighbors[2] = W::zero();
+                    neighbors[5] = W::zero();
+                }
+
+                // Add up the neighbours into the bits of their count,
+                // carrying from one bit-plane to the next.
+                let mut sum = [W::zero(); 4];
+                for &neighbor in neighbors.iter() {
+                    let mut carry = neighbor;
+                    for plane in sum.iter_mut() {
+                        let next_carry = *plane & carry;
+                        *plane = *plane ^ carry;
+                        carry = next_carry;
+                    }
+                }
+
+                let mut alive = W::zero();
+                for (n, &(birth, survival)) in self.outcomes.iter().enumerate() {
+                    if !birth && !survival {
+                        continue;
+                    }
+                    let equal = sum
+                        .iter()
+                        .enumerate()
+                        .fold(!W::zero(), |equal, (i, &plane)| equal & if n & 1 << i != 0 { plane } else { !plane });
+                    let born = if birth { !centre } else { W::zero() };
+                    let survived = if survival { centre } else { W::zero() };
+                    alive = alive | equal & (born | survived);
+                }
+
+                alive.store(alive_words);
+                for (i, &word) in alive_words.iter().enumerate() {
+                    let start = bit + 64 * i;
+                    if start < width as usize {
+                        let len = (width as usize - start).min(64);
+                        write_bits(blocks, (row - rows.start) * width as usize + start, word, len);
+                    }
+                }
+            }
+
+            std::mem::swap(&mut north, &mut middle);
+            std::mem::swap(&mut middle, &mut south);
+        }
+    }
+}
@@ -0,0 +1,253 @@
+//! Building a universe of a given size, rule and topology, filled with a
+//! random soup or a pattern, with every setting checked before anything is
+//! built.
+
+use wasm_bindgen::prelude::*;
+
+use crate::error::Error;
+use crate::patterns;
+use crate::rule::Rule;
+use crate::soup::Soup;
+use crate::topology::Topology;
+use crate::utils;
+use crate::{check_size, Universe};
+
+/// The size of a universe unless set otherwise.
+const DEFAULT_SIZE: u32 = 64;
+
+/// Settings for a new universe, checked by `build`. A universe is 64×64,
+/// on a torus, with the rule B3/S23 and every cell dead, unless set
+/// otherwise.
+///
+/// From JavaScript, the settings can also be given as a plain object, see
+/// `from_config`.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct UniverseBuilder {
+    size: Option<(u32, u32)>,
+    rule: Option<String>,
+    boundary: Option<String>,
+    soup: Option<(Option<u64>, f64)>,
+    pattern: Option<String>,
+}
+
+#[wasm_bindgen]
+impl UniverseBuilder {
+    pub fn new() -> UniverseBuilder {
+        UniverseBuilder::default()
+    }
+
+    /// Set the width and height of the universe.
+    pub fn size(mut self, width: u32, height: u32) -> UniverseBuilder {
+        self.size = Some((width, height));
+        self
+    }
+
+    /// Set the rule of the universe from a rulestring, see
+    /// `Universe::set_rule`. Without one, the rule of an RLE pattern is
+    /// used if it has one.
+    pub fn rule(mut self, rule: &str) -> UniverseBuilder {
+        self.rule = Some(rule.to_string());
+        self
+    }
+
+    /// Set how the edges of the universe are joined from a Golly grid
+    /// specification such as `T64,64` or `P30,20`, see
+    /// `Universe::set_topology`. Its size is the size of the universe
+    /// unless `size` sets it, in which case both must agree.
+    pub fn boundary(mut self, boundary: &str) -> UniverseBuilder {
+        self.boundary = Some(boundary.to_string());
+        self
+    }
+
+    /// Fill the universe with a random soup from a seed, or a seed drawn
+    /// from the platform without one, where cells are alive with a chance
+    /// of `density`, see `Universe::reset_with`.
+    pub fn soup(mut self, seed: Option<u64>, density: f64) -> UniverseBuilder {
+        self.soup = Some((seed, density));
+        self
+    }
+
+    /// Place a pattern in RLE or plain text in the middle of the universe.
+    pub fn pattern(mut self, pattern: &str) -> UniverseBuilder {
+        self.pattern = Some(pattern.to_string());
+        self
+    }
+
+    /// Build the universe, or fail with the first setting that is invalid
+    /// or does not fit with the others.
+    pub fn build(&self) -> Result<Universe, Error> {
+        utils::set_panic_hook();
+
+        let (topology, width, height) = match (&self.boundary, self.size) {
+            (Some(boundary), size) => {
+                let (topology, width, height) = Topology::parse(boundary)?;
+                match size {
+                    Some(size) if size != (width, height) => {
+                        return Err(Error::InvalidSize(format!(
+                            "grid '{}' is {}×{}, but the universe is {}×{}",
+                            boundary, width, height, size.0, size.1
+                        )));
+                    }
+                    _ => (topology, width, height),
+                }
+            }
+            (None, size) => {
+                let (width, height) = size.unwrap_or((DEFAULT_SIZE, DEFAULT_SIZE));
+                (Topology::default(), width, height)
+            }
+        };
+        check_size(width, height)?;
+
+        let pattern = match &self.pattern {
+            Some(_) if self.soup.is_some() => {
+                return Err(Error::InvalidArgument(
+                    "a universe starts from either a soup or a pattern, not both".to_string(),
+                ));
+            }
+            Some(pattern) => Some(patterns::parse(pattern, width, height)?),
+            None => None,
+        };
+        let rule: Rule = match self.rule.as_ref().or_else(|| pattern.as_ref()?.rule.as_ref()) {
+            Some(rule) => rule.parse()?,
+            None => Rule::default(),
+        };
+
+        let mut universe = Universe::empty(width, height, rule);
+        universe.replace_topology(topology);
+        if let Some((seed, density)) = self.soup {
+            let soup = Soup { density, ..seed.map_or_else(Soup::random, Soup::new) };
+            universe.reset_with_soup(&soup)?;
+        }
+        if let Some(pattern) = pattern {
+            let (top, left) = ((height - pattern.height) / 2, (width - pattern.width) / 2);
+            let cells: Vec<_> = pattern.cells.iter().map(|&(row, column)| (top + row, left + column)).collect();
+            universe.set_cells(&cells)?;
+        }
+
+        Ok(universe)
+    }
+}
+
+/// The value of a setting read from a plain JavaScript object, see
+/// `UniverseBuilder::from_config`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigValue {
+    Number(f64),
+    BigInt(u64),
+    String(String),
+    /// A value of another type, such as a boolean or an object, which no
+    /// setting takes.
+    Other,
+}
+
+impl UniverseBuilder {
+    /// Read the settings of a universe from their names and values, which
+    /// are the keys and values of the object given to `from_config`. Fails
+    /// on a name that is not a setting, or a value of the wrong type.
+    pub fn from_settings<'a, I>(settings: I) -> Result<UniverseBuilder, Error>
+    where
+        I: IntoIterator<Item = (&'a str, ConfigValue)>,
+    {
+        let mut builder = UniverseBuilder::new();
+        let (mut width, mut height, mut seed, mut density) = (None, None, None, None);
+        for (key, value) in settings {
+            let string = |value: ConfigValue| match value {
+                ConfigValue::String(value) => Ok(value),
+                _ => Err(Error::InvalidArgument(format!("'{}' must be a string", key))),
+            };
+            let number = |value: ConfigValue| match value {
+                ConfigValue::Number(value) => Ok(value),
+                _ => Err(Error::InvalidArgument(format!("'{}' must be a number", key))),
+            };
+            let size = |value: ConfigValue| match number(value)? {
+                size if size.fract() != 0.0 || !(0.0..=u32::MAX as f64).contains(&size) => {
+                    Err(Error::InvalidArgument(format!("'{}' must be a whole number of cells, not {}", key, size)))
+                }
+                size => Ok(size as u32),
+            };
+
+            match key {
+                "width" => width = Some(size(value)?),
+                "height" => height = Some(size(value)?),
+                "rule" => builder.rule = Some(string(value)?),
+                "boundary" => builder.boundary = Some(string(value)?),
+                "pattern" => builder.pattern = Some(string(value)?),
+                "density" => density = Some(number(value)?),
+                "seed" => {
+                    seed = Some(match value {
+                        ConfigValue::BigInt(seed) => seed,
+                        ConfigValue::Number(seed) if seed.fract() == 0.0 && (0.0..=(1u64 << 53) as f64).contains(&seed) => {
+                            seed as u64
+                        }
+                        _ => {
+                            return Err(Error::InvalidArgument(
+                                "'seed' must be a whole number from 0 to 2^53, or a BigInt".to_string(),
+                            ));
+                        }
+                    })
+                }
+                _ => {
+                    return Err(Error::InvalidArgument(format!(
+                        "unknown setting '{}', expected width, height, rule, boundary, seed, density or pattern",
+                        key
+                    )));
+                }
+            }
+        }
+
+        match (width, height) {
+            (Some(width), Some(height)) => builder = builder.size(width, height),
+            (None, None) => {}
+            _ => return Err(Error::InvalidArgument("'width' and 'height' must be given together".to_string())),
+        }
+        if seed.is_some() || density.is_some() {
+            builder.soup = Some((seed, density.unwrap_or(0.5)));
+        }
+
+        Ok(builder)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+impl UniverseBuilder {
+    /// Read the settings of a universe from a plain object, such as
+    /// `{ width: 128, height: 96, rule: "B36/S23", boundary: "P128,96",
+    /// seed: 42, density: 0.3 }` or `{ pattern: "bo$2bo$3o!" }`. The keys
+    /// are the settings of the builder: `width` and `height`, `rule`,
+    /// `boundary`, `seed` and `density`, and `pattern`, and any other key
+    /// is refused. A `seed` or `density` alone makes a soup, with a density
+    /// of 0.5 by default. Keys that are `null` or `undefined` are left out.
+    pub fn from_config(config: &JsValue) -> Result<UniverseBuilder, Error> {
+        use std::convert::TryFrom;
+        use wasm_bindgen::JsCast;
+
+        if !config.is_object() {
+            return Err(Error::InvalidArgument("the settings of a universe must be an object".to_string()));
+        }
+
+        let mut settings = Vec::new();
+        for key in js_sys::Object::keys(config.unchecked_ref::<js_sys::Object>()).iter() {
+            let key = key.as_string().unwrap_or_default();
+            let value = js_sys::Reflect::get(config, &key.as_str().into())
+                .map_err(|_| Error::InvalidArgument(format!("cannot read '{}'", key)))?;
+            let value = if value.is_null_or_undefined() {
+                continue;
+            } else if let Some(value) = value.as_string() {
+                ConfigValue::String(value)
+            } else if value.is_bigint() {
+                let value = u64::try_from(value)
+                    .map_err(|_| Error::InvalidArgument(format!("'{}' must fit in 64 bits", key)))?;
+                ConfigValue::BigInt(value)
+            } else if let Some(value) = value.as_f64() {
+                ConfigValue::Number(value)
+            } else {
+                ConfigValue::Other
+            };
+            settings.push((key, value));
+        }
+
+        UniverseBuilder::from_settings(settings.iter().map(|(key, value)| (key.as_str(), value.clone())))
+    }
+}
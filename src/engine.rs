@@ -0,0 +1,200 @@
+//! The storage and generation step behind a universe, so that dense,
+//! sparse and Hashlife universes are interchangeable.
+//!
+//! A `Universe` shows the cells of any `Engine` in its rows and columns,
+//! and computes them itself unless another engine is plugged in, see
+//! `Universe::replace_engine`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::Error;
+use crate::hashlife::Hashlife;
+use crate::rule::Rule;
+use crate::sparse::SparseUniverse;
+
+/// Cells that are dead or alive, by row and column. Unbounded grids have
+/// negative rows and columns too.
+pub trait Grid {
+    /// Returns whether the cell in a row and column is alive.
+    fn get(&self, row: i64, column: i64) -> bool;
+
+    /// Set the cell in a row and column to be dead or alive, or fail
+    /// without changing anything if the grid cannot hold the cell.
+    fn set(&mut self, row: i64, column: i64, alive: bool) -> Result<(), Error>;
+
+    /// Set every cell to be dead.
+    fn clear(&mut self);
+
+    /// Get the number of live cells, up to `u64::MAX`.
+    fn population(&self) -> u64;
+
+    /// Get the smallest rectangle holding every live cell, as its top row,
+    /// left column, bottom row and right column, or `None` if every cell is
+    /// dead.
+    fn bounding_box(&self) -> Option<(i64, i64, i64, i64)>;
+
+    /// Get the row and column of every live cell, row by row.
+    fn live_cells(&self) -> Vec<(i64, i64)>;
+}
+
+/// A grid that computes its generations under a rule. Engines are `Send`,
+/// so that a universe holding one can still be moved across threads.
+pub trait Engine: Grid + Send {
+    /// Get the name of the backend, such as `dense`, `sparse` or
+    /// `hashlife`.
+    fn name(&self) -> &str;
+
+    /// Get the rule of the grid.
+    fn get_rule(&self) -> &Rule;
+
+    /// Replace the rule of the grid, returning the previous one, or an
+    /// error if the engine does not support the rule.
+    fn replace_rule(&mut self, rule: Rule) -> Result<Rule, Error>;
+
+    /// Advance the grid by a number of generations.
+    fn step(&mut self, generations: u64) -> Result<(), Error>;
+}
+
+/// The engines a universe can be built on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// A bitset of the cells in view, computed with the algorithm set by
+    /// `Universe::set_algorithm`, see `Universe`.
+    #[default]
+    Dense,
+    /// An unbounded plane of chunks around the live cells, see
+    /// `SparseUniverse`.
+    Sparse,
+    /// An unbounded plane of memoised quadtrees, see `Hashlife`.
+    Hashlife,
+}
+
+impl Backend {
+    /// Create an empty engine of the backend, or nothing for the dense
+    /// backend, which the universe computes itself.
+    pub fn create(self) -> Option<Box<dyn Engine>> {
+        match self {
+            Backend::Dense => None,
+            Backend::Sparse => Some(Box::new(SparseUniverse::new())),
+            Backend::Hashlife => Some(Box::new(Hashlife::new())),
+        }
+    }
+}
+
+impl FromStr for Backend {
+    type Err = Error;
+
+    /// Parse the name of a backend: `dense`, `sparse` or `hashlife`.
+    fn from_str(s: &str) -> Result<Backend, Error> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "dense" => Ok(Backend::Dense),
+            "sparse" => Ok(Backend::Sparse),
+            "hashlife" => Ok(Backend::Hashlife),
+            _ => Err(Error::ParseError(format!("unknown backend '{}', expected dense, sparse or hashlife", s))),
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Backend::Dense => "dense",
+            Backend::Sparse => "sparse",
+            Backend::Hashlife => "hashlife",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Grid for SparseUniverse {
+    fn get(&self, row: i64, column: i64) -> bool {
+        self.is_alive(row, column)
+    }
+
+    fn set(&mut self, row: i64, column: i64, alive: bool) -> Result<(), Error> {
+        self.set_cell(row, column, alive);
+        Ok(())
+    }
+
+    fn clear(&mut self) {
+        self.reset_all_dead();
+    }
+
+    fn population(&self) -> u64 {
+        SparseUniverse::population(self)
+    }
+
+    fn bounding_box(&self) -> Option<(i64, i64, i64, i64)> {
+        SparseUniverse::bounding_box(self)
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64)> {
+        SparseUniverse::live_cells(self)
+    }
+}
+
+impl Engine for SparseUniverse {
+    fn name(&self) -> &str {
+        "sparse"
+    }
+
+    fn get_rule(&self) -> &Rule {
+        SparseUniverse::get_rule(self)
+    }
+
+    fn replace_rule(&mut self, rule: Rule) -> Result<Rule, Error> {
+        SparseUniverse::replace_rule(self, rule)
+    }
+
+    fn step(&mut self, generations: u64) -> Result<(), Error> {
+        for _ in 0..generations {
+            self.tick();
+        }
+        Ok(())
+    }
+}
+
+impl Grid for Hashlife {
+    fn get(&self, row: i64, column: i64) -> bool {
+        self.is_alive(row, column)
+    }
+
+    fn set(&mut self, row: i64, column: i64, alive: bool) -> Result<(), Error> {
+        self.set_cell(row, column, alive)
+    }
+
+    fn clear(&mut self) {
+        self.reset_all_dead();
+    }
+
+    fn population(&self) -> u64 {
+        Hashlife::population(self)
+    }
+
+    fn bounding_box(&self) -> Option<(i64, i64, i64, i64)> {
+        Hashlife::bounding_box(self)
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64)> {
+        Hashlife::live_cells(self)
+    }
+}
+
+impl Engine for Hashlife {
+    fn name(&self) -> &str {
+        "hashlife"
+    }
+
+    fn get_rule(&self) -> &Rule {
+        Hashlife::get_rule(self)
+    }
+
+    fn replace_rule(&mut self, rule: Rule) -> Result<Rule, Error> {
+        Hashlife::replace_rule(self, rule)
+    }
+
+    fn step(&mut self, generations: u64) -> Result<(), Error> {
+        self.step_by(generations)
+    }
+}
@@ -0,0 +1,79 @@
+//! The errors of the crate, and how they reach JavaScript.
+
+use std::fmt;
+
+use wasm_bindgen::prelude::*;
+
+/// Why an operation on a universe failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// A cell outside of the universe, with nothing across the edges where
+    /// it could be found instead. For a Hashlife universe, the size is the
+    /// square around the origin that it can reach.
+    OutOfBounds { row: i64, column: i64, width: u64, height: u64 },
+    /// A rulestring that is not a valid rule.
+    InvalidRule(String),
+    /// A valid rule that the engine holding the cells cannot compute.
+    UnsupportedRule(String),
+    /// Text that cannot be parsed: a grid specification, a pattern, or the
+    /// name of an algorithm, backend, anchor or symmetry.
+    ParseError(String),
+    /// A size that a universe cannot have.
+    InvalidSize(String),
+    /// A setting that is out of range or does not fit with the others.
+    InvalidArgument(String),
+    /// An engine that could not advance by the generations asked for.
+    StepFailed(String),
+}
+
+impl Error {
+    /// Get the name of the kind of error, such as `OutOfBounds`, which
+    /// JavaScript gets as the `code` of the exception.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::OutOfBounds { .. } => "OutOfBounds",
+            Error::InvalidRule(_) => "InvalidRule",
+            Error::UnsupportedRule(_) => "UnsupportedRule",
+            Error::ParseError(_) => "ParseError",
+            Error::InvalidSize(_) => "InvalidSize",
+            Error::InvalidArgument(_) => "InvalidArgument",
+            Error::StepFailed(_) => "StepFailed",
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::OutOfBounds { row, column, width, height } => {
+                write!(f, "row {}, column {} is outside of the {}×{} universe", row, column, width, height)
+            }
+            Error::InvalidRule(message)
+            | Error::UnsupportedRule(message)
+            | Error::ParseError(message)
+            | Error::InvalidSize(message)
+            | Error::InvalidArgument(message)
+            | Error::StepFailed(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Errors are thrown in JavaScript as an `Error` named `UniverseError`,
+/// with the message of the error and its `code`, see `Error::code`.
+impl From<Error> for JsValue {
+    #[cfg(target_arch = "wasm32")]
+    fn from(error: Error) -> JsValue {
+        let exception = js_sys::Error::new(&error.to_string());
+        exception.set_name("UniverseError");
+        // Setting a property of a new object cannot fail.
+        let _ = js_sys::Reflect::set(&exception, &"code".into(), &error.code().into());
+        exception.into()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn from(error: Error) -> JsValue {
+        JsValue::from_str(&error.to_string())
+    }
+}
@@ -0,0 +1,623 @@
+//! Hashlife, which runs patterns exponentially far ahead by memoizing the
+//! evolution of the regions they are made of.
+//!
+//! The plane is a quadtree: a node of level `k` is a square of `2^k` cells
+//! made of 4 nodes of level `k - 1`, down to single cells at level 0. Nodes
+//! are canonical, so equal regions are a single node however often and
+//! wherever they appear, and the result of advancing the centre of a node
+//! is computed once and remembered. The centre of a node of level `k` can
+//! be advanced up to `2^(k - 2)` generations at once, since nothing from
+//! outside the node reaches it in that time.
+//!
+//! See Bill Gosper, "Exploiting Regularities in Large Cellular Spaces",
+//! Physica D, 1984.
+
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::error::Error;
+use crate::patterns;
+use crate::rule::{config_bit, Rule};
+use crate::sparse::check_rule;
+use crate::utils;
+use crate::{Timer, Universe};
+
+type NodeId = u32;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+/// The level of the largest root, whose cells span all signed 64-bit
+/// coordinates but the outermost ones.
+const MAX_LEVEL: u32 = 63;
+
+/// The largest power of two a single step can advance by, leaving room to
+/// grow the root around the pattern.
+pub const MAX_STEP_LOG2: u32 = MAX_LEVEL - 3;
+
+/// The number of nodes above which the node cache is garbage collected after
+/// a step, unless set with `Hashlife::set_node_limit`.
+pub const DEFAULT_NODE_LIMIT: usize = 1 << 20;
+
+/// Fail if a cell lies more than `2^62` cells away from the origin, beyond
+/// the largest root.
+fn check_reach(row: i64, column: i64) -> Result<(), Error> {
+    let half = 1i64 << (MAX_LEVEL - 1);
+    if (-half..half).contains(&row) && (-half..half).contains(&column) {
+        Ok(())
+    } else {
+        Err(Error::OutOfBounds { row, column, width: 1 << MAX_LEVEL, height: 1 << MAX_LEVEL })
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Node {
+    level: u32,
+    // The north-west, north-east, south-west and south-east quadrants, or
+    // nothing for single cells.
+    children: [NodeId; 4],
+    population: u64,
+}
+
+/// A universe without edges, advanced with the Hashlife algorithm. Like
+/// `SparseUniverse`, only rules that look at the 8 immediate neighbours, or
+/// the 6 of a hexagonal grid, with two states and without B0 are
+/// supported.
+#[wasm_bindgen]
+pub struct Hashlife {
+    nodes: Vec<Node>,
+    // The node made of the given quadrants, so that every region is a
+    // single node.
+    canonical: HashMap<[NodeId; 4], NodeId>,
+    // The centre of a node advanced by a power of two generations.
+    results: HashMap<(NodeId, u32), NodeId>,
+    // The empty node of every level computed so far.
+    empty: Vec<NodeId>,
+    // The whole universe, centred on the origin: a root of level `k` spans
+    // the rows and columns from `-2^(k - 1)` to `2^(k - 1) - 1`.
+    root: NodeId,
+    generation: u64,
+    node_limit: usize,
+    rule: Rule,
+}
+
+impl Hashlife {
+    fn leaves() -> Vec<Node> {
+        vec![
+            Node { level: 0, children: [DEAD; 4], population: 0 },
+            Node { level: 0, children: [DEAD; 4], population: 1 },
+        ]
+    }
+
+    fn level(&self, id: NodeId) -> u32 {
+        self.nodes[id as usize].level
+    }
+
+    fn children(&self, id: NodeId) -> [NodeId; 4] {
+        self.nodes[id as usize].children
+    }
+
+    /// Get the canonical node made of 4 quadrants of the same level.
+    fn join(&mut self, children: [NodeId; 4]) -> NodeId {
+        if let Some(&id) = self.canonical.get(&children) {
+            return id;
+        }
+
+        let id = self.nodes.len() as NodeId;
+        let level = self.level(children[0]) + 1;
+        let population = children
+            .iter()
+            .fold(0u64, |population, &child| population.saturating_add(self.nodes[child as usize].population));
+        self.nodes.push(Node { level, children, population });
+        self.canonical.insert(children, id);
+        id
+    }
+
+    fn empty(&mut self, level: u32) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let e = *self.empty.last().unwrap();
+            let next = self.join([e; 4]);
+            self.empty.push(next);
+        }
+
+        self.empty[level as usize]
+    }
+
+    /// The node of the level below made of the 4 grandchildren around the
+    /// middle of a node.
+    fn centre(&mut self, id: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.children(id);
+        self.join([self.children(nw)[3], self.children(ne)[2], self.children(sw)[1], self.children(se)[0]])
+    }
+
+    /// The node straddling two nodes side by side.
+    fn centre_horizontal(&mut self, west: NodeId, east: NodeId) -> NodeId {
+        let ([_, wne, _, wse], [enw, _, esw, _]) = (self.children(west), self.children(east));
+        self.join([wne, enw, wse, esw])
+    }
+
+    /// The node straddling two nodes one above the other.
+    fn centre_vertical(&mut self, north: NodeId, south: NodeId) -> NodeId {
+        let ([_, _, nsw, nse], [snw, sne, _, _]) = (self.children(north), self.children(south));
+        self.join([nsw, nse, snw, sne])
+    }
+
+    /// Surround a node with empty space, keeping its middle in place.
+    fn expand(&mut self, id: NodeId) -> NodeId {
+        let e = self.empty(self.level(id) - 1);
+        let [nw, ne, sw, se] = self.children(id);
+        let children = [
+            self.join([e, e, e, nw]),
+            self.join([e, e, ne, e]),
+            self.join([e, sw, e, e]),
+            self.join([se, e, e, e]),
+        ];
+        self.join(children)
+    }
+
+    /// Returns whether every live cell of a node lies within the quarter
+    /// around its middle.
+    fn is_centred(&self, id: NodeId) -> bool {
+        let [nw, ne, sw, se] = self.children(id);
+        let inner = [self.children(nw)[3], self.children(ne)[2], self.children(sw)[1], self.children(se)[0]];
+        let population = inner.iter().map(|&child| self.nodes[child as usize].population).sum::<u64>();
+        population == self.nodes[id as usize].population
+    }
+
+    /// Advance the 2×2 middle of a 4×4 node by one generation.
+    fn advance_leaf(&mut self, id: NodeId) -> NodeId {
+        let mut cells = [[false; 4]; 4];
+        for (i, &quadrant) in self.children(id).iter().enumerate() {
+            for (j, &cell) in self.children(quadrant).iter().enumerate() {
+                cells[i / 2 * 2 + j / 2][i % 2 * 2 + j % 2] = cell == ALIVE;
+            }
+        }
+
+        let mut next = [DEAD; 4];
+        for (i, cell) in next.iter_mut().enumerate() {
+            let (row, column) = (1 + i / 2, 1 + i % 2);
+            let mut config = 0;
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    if cells[(row as i32 + dy) as usize][(column as i32 + dx) as usize] {
+                        config |= config_bit(dy, dx);
+                    }
+                }
+            }
+            if self.rule.next_state_from_config(config) {
+                *cell = ALIVE;
+            }
+        }
+
+        self.join(next)
+    }
+
+    /// Advance the middle half of a node of level `k` by `2^step_log2`
+    /// generations, where `step_log2` is at most `k - 2`.
+    fn advance(&mut self, id: NodeId, step_log2: u32) -> NodeId {
+        let node = self.nodes[id as usize];
+        if node.population == 0 {
+            return self.empty(node.level - 1);
+        }
+        if let Some(&result) = self.results.get(&(id, step_log2)) {
+            return result;
+        }
+
+        let result = if node.level == 2 {
+            self.advance_leaf(id)
+        } else {
+            // The 9 overlapping nodes of the level below covering the node,
+            // each narrowed down to its middle half, either advanced by half
+            // the step at full speed, or not advanced at all for shorter
+            // steps. The middle halves of the 4 nodes they form are then
+            // advanced by the rest of the step.
+            let [nw, ne, sw, se] = node.children;
+            let parts = [
+                nw,
+                self.centre_horizontal(nw, ne),
+                ne,
+                self.centre_vertical(nw, sw),
+                self.centre(id),
+                self.centre_vertical(ne, se),
+                sw,
+                self.centre_horizontal(sw, se),
+                se,
+            ];
+
+            let full_speed = step_log2 == node.level - 2;
+            let rest = if full_speed { step_log2 - 1 } else { step_log2 };
+            let mut inner = [DEAD; 9];
+            for (inner, &part) in inner.iter_mut().zip(parts.iter()) {
+                *inner = if full_speed { self.advance(part, rest) } else { self.centre(part) };
+            }
+
+            let mut quadrants = [DEAD; 4];
+            for (i, quadrant) in quadrants.iter_mut().enumerate() {
+                let first = i / 2 * 3 + i % 2;
+                let joined = self.join([inner[first], inner[first + 1], inner[first + 3], inner[first + 4]]);
+                *quadrant = self.advance(joined, rest);
+            }
+            self.join(quadrants)
+        };
+
+        self.results.insert((id, step_log2), result);
+        result
+    }
+
+    /// Grow the root until it holds a cell, or fail without growing it if
+    /// the cell is out of reach.
+    fn reach(&mut self, row: i64, column: i64) -> Result<(), Error> {
+        check_reach(row, column)?;
+        loop {
+            let half = 1i64 << (self.level(self.root) - 1);
+            if (-half..half).contains(&row) && (-half..half).contains(&column) {
+                return Ok(());
+            }
+            self.root = self.expand(self.root);
+        }
+    }
+
+    /// Set a cell of a node, given its position from the top-left corner of
+    /// the node.
+    fn set_in(&mut self, id: NodeId, row: u64, column: u64, alive: bool) -> NodeId {
+        let level = self.level(id);
+        if level == 0 {
+            return if alive { ALIVE } else { DEAD };
+        }
+
+        let half = 1 << (level - 1);
+        let i = (row >= half) as usize * 2 + (column >= half) as usize;
+        let mut children = self.children(id);
+        children[i] = self.set_in(children[i], row % half, column % half, alive);
+        self.join(children)
+    }
+
+    /// Set a cell to be dead or alive, or fail if it is out of reach, see
+    /// `check_reach`.
+    pub(crate) fn set_cell(&mut self, row: i64, column: i64, alive: bool) -> Result<(), Error> {
+        self.reach(row, column)?;
+
+        let half = 1i64 << (self.level(self.root) - 1);
+        let (r, c) = ((row + half) as u64, (column + half) as u64);
+        self.root = self.set_in(self.root, r, c, alive);
+        Ok(())
+    }
+
+    /// Set the cells of a square pattern, given row by row, around a cell,
+    /// or fail without setting any of them if one is out of reach.
+    fn stamp(&mut self, row: i64, column: i64, pattern: &[bool]) -> Result<(), Error> {
+        check_reach(row, column)?;
+        let size = (pattern.len() as f64).sqrt() as usize;
+        let half = (size / 2) as i64;
+
+        let cells: Vec<_> = pattern
+            .iter()
+            .enumerate()
+            .map(|(i, &alive)| (row - half + (i / size) as i64, column - half + (i % size) as i64, alive))
+            .collect();
+        for &(r, c, _) in &cells {
+            check_reach(r, c)?;
+        }
+        for (r, c, alive) in cells {
+            self.set_cell(r, c, alive)?;
+        }
+        Ok(())
+    }
+
+    /// Advance the universe by `2^step_log2` generations.
+    fn step(&mut self, step_log2: u32) -> Result<(), Error> {
+        // Nothing can travel faster than one cell per generation, so a
+        // pattern within the middle quarter of a root twice the size needed
+        // stays within the middle half that is advanced.
+        let mut root = self.root;
+        while self.level(root) < step_log2 + 2 || !self.is_centred(root) {
+            if self.level(root) == MAX_LEVEL {
+                return Err(Error::StepFailed("the pattern has grown out of reach".to_string()));
+            }
+            root = self.expand(root);
+        }
+        if self.level(root) == MAX_LEVEL {
+            return Err(Error::StepFailed("the pattern has grown out of reach".to_string()));
+        }
+        root = self.expand(root);
+
+        self.root = self.advance(root, step_log2);
+        self.generation += 1 << step_log2;
+        Ok(())
+    }
+
+    /// The bounds of the live cells of a node from its top-left corner, as
+    /// in `bounding_box`.
+    fn bounds(&self, id: NodeId, memo: &mut HashMap<NodeId, Bounds>) -> Bounds {
+        let node = self.nodes[id as usize];
+        if node.population == 0 {
+            return None;
+        }
+        if node.level == 0 {
+            return Some((0, 0, 0, 0));
+        }
+        if let Some(&bounds) = memo.get(&id) {
+            return bounds;
+        }
+
+        let half = 1i64 << (node.level - 1);
+        let mut bounds: Bounds = None;
+        for (i, &child) in node.children.iter().enumerate() {
+            let (dy, dx) = ((i / 2) as i64 * half, (i % 2) as i64 * half);
+            if let Some((top, left, bottom, right)) = self.bounds(child, memo) {
+                let (top, left, bottom, right) = (top + dy, left + dx, bottom + dy, right + dx);
+                bounds = Some(match bounds {
+                    Some((t, l, b, r)) => (t.min(top), l.min(left), b.max(bottom), r.max(right)),
+                    None => (top, left, bottom, right),
+                });
+            }
+        }
+
+        memo.insert(id, bounds);
+        bounds
+    }
+
+    /// Collect the live cells of a node whose top-left corner is at a row
+    /// and column.
+    fn collect(&self, id: NodeId, top: i64, left: i64, cells: &mut Vec<(i64, i64)>) {
+        let node = self.nodes[id as usize];
+        if node.population == 0 {
+            return;
+        }
+        if node.level == 0 {
+            cells.push((top, left));
+            return;
+        }
+
+        let half = 1i64 << (node.level - 1);
+        for (i, &child) in node.children.iter().enumerate() {
+            self.collect(child, top + (i / 2) as i64 * half, left + (i % 2) as i64 * half, cells);
+        }
+    }
+
+    fn copy_from(&mut self, nodes: &[Node], id: NodeId, copies: &mut HashMap<NodeId, NodeId>) -> NodeId {
+        if id == DEAD || id == ALIVE {
+            return id;
+        }
+        if let Some(&copy) = copies.get(&id) {
+            return copy;
+        }
+
+        let mut children = nodes[id as usize].children;
+        for child in children.iter_mut() {
+            *child = self.copy_from(nodes, *child, copies);
+        }
+        let copy = self.join(children);
+        copies.insert(id, copy);
+        copy
+    }
+}
+
+type Bounds = Option<(i64, i64, i64, i64)>;
+
+/// Public methods, exported to JavaScript.
+#[wasm_bindgen]
+impl Hashlife {
+    /// Create an empty universe following Conway's Game of Life.
+    pub fn new() -> Hashlife {
+        utils::set_panic_hook();
+
+        let mut hashlife = Hashlife {
+            nodes: Hashlife::leaves(),
+            canonical: HashMap::new(),
+            results: HashMap::new(),
+            empty: vec![DEAD],
+            root: DEAD,
+            generation: 0,
+            node_limit: DEFAULT_NODE_LIMIT,
+            rule: Rule::default(),
+        };
+        hashlife.root = hashlife.empty(3);
+        hashlife
+    }
+
+    /// Advance the universe by a number of generations, in steps of the
+    /// powers of two it is made of. Powers of two are the fastest, with
+    /// steps up to `2^MAX_STEP_LOG2`. Fails if the pattern grows out of
+    /// reach of signed 64-bit coordinates, having advanced by the steps
+    /// before.
+    pub fn step_by(&mut self, generations: u64) -> Result<(), Error> {
+        let _timer = Timer::new("Hashlife::step_by");
+
+        if generations >> (MAX_STEP_LOG2 + 1) != 0 {
+            return Err(Error::StepFailed(format!(
+                "cannot step by more than 2^{} generations at once",
+                MAX_STEP_LOG2 + 1
+            )));
+        }
+
+        for step_log2 in (0..=MAX_STEP_LOG2).rev().filter(|&k| generations & 1 << k != 0) {
+            self.step(step_log2)?;
+        }
+
+        if self.nodes.len() > self.node_limit {
+            self.garbage_collect();
+        }
+        Ok(())
+    }
+
+    /// Get the number of generations the universe has been advanced by.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn reset_all_dead(&mut self) {
+        self.root = self.empty(3);
+        self.generation = 0;
+    }
+
+    /// Get the rule of the universe in B/S notation.
+    pub fn rule(&self) -> String {
+        self.rule.to_string()
+    }
+
+    /// Set the rule of the universe from a rulestring, as for
+    /// `SparseUniverse::set_rule`.
+    pub fn set_rule(&mut self, rule: &str) -> Result<(), Error> {
+        let rule: Rule = rule.parse()?;
+        self.replace_rule(rule)?;
+        Ok(())
+    }
+
+    /// Returns whether the cell in a row and column is alive.
+    pub fn is_alive(&self, row: i64, column: i64) -> bool {
+        let mut id = self.root;
+        let half = 1i64 << (self.level(id) - 1);
+        if !(-half..half).contains(&row) || !(-half..half).contains(&column) {
+            return false;
+        }
+
+        let (mut r, mut c) = ((row + half) as u64, (column + half) as u64);
+        while self.level(id) > 0 {
+            let half = 1 << (self.level(id) - 1);
+            id = self.children(id)[(r >= half) as usize * 2 + (c >= half) as usize];
+            r %= half;
+            c %= half;
+        }
+        id == ALIVE
+    }
+
+    /// Toggle a cell between dead and alive. Fails if the cell lies more
+    /// than `2^62` cells away from the origin.
+    pub fn toggle_cell(&mut self, row: i64, column: i64) -> Result<(), Error> {
+        let alive = self.is_alive(row, column);
+        self.set_cell(row, column, !alive)
+    }
+
+    /// Insert a glider around a cell. Fails without inserting anything if
+    /// part of it lies more than `2^62` cells away from the origin.
+    pub fn insert_glider(&mut self, row: i64, column: i64) -> Result<(), Error> {
+        self.stamp(row, column, &patterns::GLIDER)
+    }
+
+    /// Insert a pulsar around a cell, see `insert_glider`.
+    pub fn insert_pulsar(&mut self, row: i64, column: i64) -> Result<(), Error> {
+        self.stamp(row, column, &patterns::PULSAR)
+    }
+
+    /// Get the number of live cells, up to `u64::MAX`.
+    pub fn population(&self) -> u64 {
+        self.nodes[self.root as usize].population
+    }
+
+    /// Get the number of nodes in the cache.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Set the number of nodes above which the node cache is garbage
+    /// collected after a step, see `DEFAULT_NODE_LIMIT`.
+    pub fn set_node_limit(&mut self, limit: usize) {
+        self.node_limit = limit;
+    }
+
+    /// Free the nodes that are no longer part of the universe, along with
+    /// the results remembered for them.
+    pub fn garbage_collect(&mut self) {
+        let nodes = std::mem::replace(&mut self.nodes, Hashlife::leaves());
+        self.canonical.clear();
+        self.empty.truncate(1);
+
+        let mut copies = HashMap::new();
+        self.root = self.copy_from(&nodes, self.root, &mut copies);
+
+        let results = std::mem::take(&mut self.results);
+        for ((id, step_log2), result) in results {
+            if let (Some(&id), Some(&result)) = (copies.get(&id), copies.get(&result)) {
+                self.results.insert((id, step_log2), result);
+            }
+        }
+    }
+}
+
+impl Hashlife {
+    /// Create a universe holding the cells of a dense universe, in the same
+    /// rows and columns. Its edges are not carried over, so patterns
+    /// crossing them evolve differently. Fails if the universe does not
+    /// support the rule, see `set_rule`.
+    pub fn from_universe(universe: &Universe) -> Result<Hashlife, Error> {
+        let mut hashlife = Hashlife::new();
+        hashlife.replace_rule(universe.get_rule().clone())?;
+
+        let width = universe.width() as usize;
+        let blocks = universe.get_cells();
+        for i in (0..width * universe.height() as usize).filter(|&i| blocks[i / 32] & 1 << (i % 32) != 0) {
+            hashlife.set_cell((i / width) as i64, (i % width) as i64, true)?;
+        }
+        Ok(hashlife)
+    }
+
+    /// Copy the cells of a `width` × `height` rectangle, from the given top
+    /// row and left column, into a dense universe with the same rule.
+    pub fn to_universe(&self, top: i64, left: i64, width: u32, height: u32) -> Universe {
+        let mut universe = Universe::empty(width, height, self.rule.clone());
+        let mut cells = Vec::new();
+        for row in 0..height {
+            for column in 0..width {
+                if self.is_alive(top + row as i64, left + column as i64) {
+                    cells.push((row, column));
+                }
+            }
+        }
+        universe.set_cells(&cells).expect("the cells are in the universe");
+        universe
+    }
+
+    /// Get the rule of the universe.
+    pub fn get_rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    /// Replace the rule of the universe, returning the previous one, or an
+    /// error if the universe does not support the rule. The remembered
+    /// results no longer hold, and are cleared.
+    pub fn replace_rule(&mut self, rule: Rule) -> Result<Rule, Error> {
+        check_rule(&rule)?;
+        self.results.clear();
+        Ok(std::mem::replace(&mut self.rule, rule))
+    }
+
+    /// Set cells to be alive in a universe by passing the row and column
+    /// of each cell as an array. Fails without setting any cell if one of
+    /// them lies more than `2^62` cells away from the origin.
+    pub fn set_cells(&mut self, cells: &[(i64, i64)]) -> Result<(), Error> {
+        for &(row, column) in cells {
+            check_reach(row, column)?;
+        }
+
+        for &(row, column) in cells {
+            self.set_cell(row, column, true)?;
+        }
+        Ok(())
+    }
+
+    /// Get the row and column of every live cell, row by row.
+    pub fn live_cells(&self) -> Vec<(i64, i64)> {
+        let half = 1i64 << (self.level(self.root) - 1);
+        let mut cells = Vec::new();
+        self.collect(self.root, -half, -half, &mut cells);
+        cells.sort_unstable();
+        cells
+    }
+
+    /// Get the smallest rectangle holding every live cell, as its top row,
+    /// left column, bottom row and right column, or `None` if every cell is
+    /// dead.
+    pub fn bounding_box(&self) -> Option<(i64, i64, i64, i64)> {
+        let half = 1i64 << (self.level(self.root) - 1);
+        self.bounds(self.root, &mut HashMap::new())
+            .map(|(top, left, bottom, right)| (top - half, left - half, bottom - half, right - half))
+    }
+}
+
+impl Default for Hashlife {
+    fn default() -> Hashlife {
+        Hashlife::new()
+    }
+}
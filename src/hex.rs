@@ -0,0 +1,57 @@
+//! Geometry of hexagonal universes, for drawing and hit-testing cells.
+//!
+//! A hexagonal universe is stored on the usual square grid in axial
+//! coordinates, as in Golly: the 6 neighbours of a cell are the 3×3
+//! neighbourhood without the north-east and south-west cells. Drawn
+//! straight, the grid becomes a rhombus, so every row is shifted back by
+//! half a cell per row and wrapped around, which gives the offset
+//! coordinates of a rectangle of pointy-topped hexagons where odd rows
+//! stick out half a cell to the left.
+
+/// The width of a hexagon with the given circumradius.
+fn hex_width(size: f64) -> f64 {
+    3f64.sqrt() * size
+}
+
+/// The offset column a cell is drawn in, given its stored column.
+pub fn offset_column(row: u32, column: u32, width: u32) -> u32 {
+    let shift = (row / 2) % width;
+    (column + width - shift) % width
+}
+
+/// The stored column of a cell, given the offset column it is drawn in.
+pub fn axial_column(row: u32, offset_column: u32, width: u32) -> u32 {
+    (offset_column + row / 2) % width
+}
+
+/// The centre of the hexagon drawn in a row and offset column, with
+/// hexagons of the given circumradius and the top-left corner of the
+/// drawing at the origin.
+pub fn center(row: u32, offset_column: u32, size: f64) -> (f64, f64) {
+    let x = hex_width(size) * (offset_column as f64 + 1.0 - (row % 2) as f64 / 2.0);
+    let y = size * (1.0 + 1.5 * row as f64);
+    (x, y)
+}
+
+/// The row and offset column of the hexagon containing a point, in the
+/// layout of `center`. The result may lie outside the universe.
+pub fn cell_at(x: f64, y: f64, size: f64) -> (i64, i64) {
+    // Fractional axial coordinates of a layout with the hexagon in row 0
+    // and offset column 0 at the origin, rounded to the nearest hexagon in
+    // cube coordinates (q, r, -q - r).
+    let (x, y) = (x - hex_width(size), y - size);
+    let q = (3f64.sqrt() / 3.0 * x - y / 3.0) / size;
+    let r = 2.0 / 3.0 * y / size;
+    let s = -q - r;
+
+    let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+    let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+    if dq > dr && dq > ds {
+        rq = -rr - rs;
+    } else if dr > ds {
+        rr = -rq - rs;
+    }
+
+    let (q, r) = (rq as i64, rr as i64);
+    (r, q + (r + 1).div_euclid(2))
+}
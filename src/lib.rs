@@ -1,39 +1,87 @@
+#[cfg(target_arch = "wasm32")]
 extern crate js_sys;
 extern crate fixedbitset;
+#[cfg(target_arch = "wasm32")]
 extern crate web_sys;
 
+mod algorithm;
+mod anchor;
+mod bitwise;
+mod builder;
+mod engine;
+mod error;
+mod hashlife;
+mod hex;
+mod lookup;
+mod neighbors;
+mod patterns;
+pub mod platform;
+mod random;
+mod rule;
+#[cfg(feature = "simd")]
+mod simd;
+mod soup;
+mod sparse;
+mod tiles;
+mod topology;
+mod triangle;
 mod utils;
+mod verify;
+
+pub use algorithm::Algorithm;
+pub use anchor::Anchor;
+pub use builder::{ConfigValue, UniverseBuilder};
+pub use engine::{Backend, Engine, Grid};
+pub use error::Error;
+pub use hashlife::{Hashlife, DEFAULT_NODE_LIMIT, MAX_STEP_LOG2};
+pub use platform::Timer;
+pub use random::Random;
+pub use rule::{config_bit, Kernel, Neighborhood, Rule};
+pub use soup::{Soup, Symmetry};
+pub use sparse::{SparseUniverse, CHUNK_SIZE};
+pub use tiles::TILE_SIZE;
+pub use topology::{Edges, Topology};
+pub use verify::{Divergence, Lockstep};
 
 use std::fmt;
 use wasm_bindgen::prelude::*;
-use web_sys::console;
 use fixedbitset::FixedBitSet;
 
-// A macro to provide 'println!(..)'-style syntax for 'console.log' logging.
-#[allow(unused_macros)]
-macro_rules! log {
-    ( $( $t:tt )* ) => {
-        web_sys::console::log_1(&format!( $( $t )* ).into());
-    }
-}
+// The 3×3 configuration bits of the west and middle columns.
+const WEST_COLUMNS: usize = 0b110_110_110;
 
-pub struct Timer<'a> {
-    name: &'a str,
-}
+// The number of cells from which `Universe::tick` splits the universe into
+// bands computed in parallel.
+#[cfg(feature = "parallel")]
+const PARALLEL_CELLS: usize = 1 << 16;
 
-impl<'a> Timer<'a> {
-    pub fn new(name: &'a str) -> Timer<'a> {
-        console::time_with_label(name);
-        Timer { name }
+/// Check that a universe can be `width` × `height`: it has cells, and no
+/// more than can be indexed.
+fn check_size(width: u32, height: u32) -> Result<(), Error> {
+    if width == 0 || height == 0 {
+        return Err(Error::InvalidSize(format!("a {}×{} universe has no cells", width, height)));
+    }
+    if width as u64 * height as u64 > u32::MAX as u64 {
+        return Err(Error::InvalidSize(format!(
+            "a {}×{} universe has more than {} cells",
+            width,
+            height,
+            u32::MAX
+        )));
     }
+    Ok(())
 }
 
-impl<'a> Drop for Timer<'a> {
-    fn drop(&mut self) {
-        console::time_end_with_label(self.name);
+// A macro to provide 'println!(..)'-style syntax for 'console.log' logging.
+#[allow(unused_macros)]
+macro_rules! log {
+    ( $( $t:tt )* ) => {
+        $crate::platform::log(&format!( $( $t )* ));
     }
 }
 
+/// The state of a cell. Generations rules add refractory states numbered
+/// from 2 upwards, which are exported through `Universe::cell_states`.
 #[wasm_bindgen]
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -52,20 +100,181 @@ impl Cell {
     }
 }
 
+/// A grid of cells of a given width and height, computing their
+/// generations itself with one of several algorithms, see `tick`.
+///
+/// Another engine can hold the cells instead, see `set_backend`: the
+/// universe then shows the cells of its rows and columns from 0 to the
+/// height and width, and forwards to it setting cells, stepping, counting
+/// the population and changing the rule. What only the universe itself
+/// computes is left aside while another engine holds the cells:
+///
+/// - `tick_scalar`, `tick_lookup`, `tick_active` and `tick_in_bands` have
+///   no effect, and `changed_cells` and `active_tiles` give nothing;
+/// - the algorithm set with `set_algorithm` or `replace_algorithm` is only
+///   used once the universe holds the cells itself again;
+/// - the topology only says how the cells in view are wrapped when set,
+///   since the sparse and Hashlife engines are unbounded planes;
+/// - the background stays dead, and the engine keeps track of its own.
 #[wasm_bindgen]
 pub struct Universe {
     width: u32,
     height: u32,
     cells: FixedBitSet,
+    // The state of every cell under a Generations rule, empty otherwise.
+    // `cells` always holds the live (state 1) cells.
+    states: Vec<u8>,
+    // Whether the cells outside any pattern are alive. Rules with B0 make
+    // them alive, so `cells` then holds the cells that differ from it.
+    background: bool,
+    rule: Rule,
+    topology: Topology,
+    // Where the next generation is computed, swapped with the current one
+    // afterwards so that ticking does not allocate.
+    back: Buffers,
+    algorithm: Algorithm,
+    // The lookup table of the rule, computed on the first tick that uses it.
+    lookup: Option<lookup::Table>,
+    // The tiles that changed, while the `ActiveTiles` algorithm tracks them.
+    tiles: Option<tiles::Tiles>,
+    // Another engine holding the cells and computing their generations,
+    // whose rows and columns from 0 to the height and width `cells` show.
+    engine: Option<Box<dyn Engine>>,
+    // The seed of the last random soup, see `reset_with`.
+    seed: Option<u64>,
+}
+
+/// The back buffers of a universe, and scratch space for computing the next
+/// generation.
+#[derive(Default)]
+struct Buffers {
+    cells: FixedBitSet,
+    states: Vec<u8>,
+    counts: Vec<u32>,
+    rows: Vec<u64>,
 }
 
 impl Universe {
+    /// Create a universe where every cell is dead.
+    fn empty(width: u32, height: u32, rule: Rule) -> Universe {
+        let mut universe = Universe {
+            width,
+            height,
+            cells: FixedBitSet::with_capacity((width * height) as usize),
+            states: Vec::new(),
+            background: false,
+            rule,
+            topology: Topology::default(),
+            back: Buffers::default(),
+            algorithm: Algorithm::default(),
+            lookup: None,
+            tiles: None,
+            engine: None,
+            seed: None,
+        };
+        universe.sync_states();
+        universe
+    }
+
     fn get_index(&self, row: u32, column: u32) -> usize {
         (row * self.width + column) as usize
     }
 
-    fn live_neighbor_count(&self, row: u32, column: u32) -> u8 {
-        let mut count = 0;
+    /// Set a cell in view, and in the engine plugged in if any, or fail
+    /// without changing anything if the engine cannot hold it.
+    fn set_cell(&mut self, idx: usize, alive: bool) -> Result<(), Error> {
+        if let Some(engine) = &mut self.engine {
+            let width = self.width as usize;
+            engine.set((idx / width) as i64, (idx % width) as i64, alive)?;
+        }
+        self.show_cell(idx, alive);
+        Ok(())
+    }
+
+    /// Set a cell in view only, leaving the engine plugged in as it is.
+    fn show_cell(&mut self, idx: usize, alive: bool) {
+        let set = alive != self.background;
+        if let Some(tiles) = &mut self.tiles {
+            if self.cells[idx] != set {
+                tiles.flip(idx, set);
+            }
+        }
+        self.cells.set(idx, set);
+        if !self.states.is_empty() {
+            self.states[idx] = alive as u8;
+        }
+    }
+
+    /// Show the cells of the engine plugged in, if any.
+    fn show_engine(&mut self) {
+        let engine = match &self.engine {
+            Some(engine) => engine,
+            None => return,
+        };
+
+        self.cells.clear();
+        self.tiles = None;
+        if let Some((top, left, bottom, right)) = engine.bounding_box() {
+            let (width, height) = (self.width as i64, self.height as i64);
+            for row in top.max(0)..=bottom.min(height - 1) {
+                for column in left.max(0)..=right.min(width - 1) {
+                    if engine.get(row, column) {
+                        self.cells.insert((row * width + column) as usize);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Bring the per-cell states in line with the current rule. Refractory
+    /// states that the rule no longer has become dead, and an alive
+    /// background is filled in if the rule has no B0. The lookup table of
+    /// the previous rule is dropped, and every tile counts as changed.
+    fn sync_states(&mut self) {
+        self.lookup = None;
+        self.tiles = None;
+        if self.background && !self.rule.has_b0() {
+            self.cells.toggle_range(..(self.width * self.height) as usize);
+            self.background = false;
+        }
+
+        if !self.rule.is_generations() {
+            self.states = Vec::new();
+        } else if self.states.is_empty() {
+            self.states = (0..self.cells.len()).map(|i| self.cells[i] as u8).collect();
+        } else {
+            let states = self.rule.states();
+            for state in self.states.iter_mut().filter(|state| **state >= states) {
+                *state = Cell::Dead as u8;
+            }
+        }
+    }
+
+    /// Get the algorithm that computes the next generation with the current
+    /// rule: the chosen one if it supports the rule, or else the fastest one
+    /// that does.
+    fn effective_algorithm(&self) -> Algorithm {
+        let bitwise = bitwise::supports(&self.rule);
+        let lookup = lookup::supports(&self.rule);
+        match self.algorithm {
+            Algorithm::Scalar => Algorithm::Scalar,
+            Algorithm::Bitwise if bitwise => Algorithm::Bitwise,
+            Algorithm::LookupTable if lookup => Algorithm::LookupTable,
+            Algorithm::ActiveTiles if lookup => Algorithm::ActiveTiles,
+            _ if bitwise => Algorithm::Bitwise,
+            _ if lookup => Algorithm::LookupTable,
+            _ => Algorithm::Scalar,
+        }
+    }
+
+    /// Get the configuration of the 3×3 neighbourhood around a cell, as
+    /// described by `config_bit`.
+    fn neighborhood_config(&self, row: u32, column: u32) -> usize {
+        if self.topology != Topology::Torus {
+            return self.neighborhood_config_across_edges(row, column);
+        }
+
+        let mut config = 0;
 
         let north = if row == 0 {
             self.height - 1
@@ -92,119 +301,502 @@ impl Universe {
         };
     
         let nw = self.get_index(north, west);
-        count += self.cells[nw] as u8;
+        config |= (self.cells[nw] as usize) << 8;
     
         let n = self.get_index(north, column);
-        count += self.cells[n] as u8;
+        config |= (self.cells[n] as usize) << 7;
     
         let ne = self.get_index(north, east);
-        count += self.cells[ne] as u8;
+        config |= (self.cells[ne] as usize) << 6;
     
         let w = self.get_index(row, west);
-        count += self.cells[w] as u8;
+        config |= (self.cells[w] as usize) << 5;
+    
+        let c = self.get_index(row, column);
+        config |= (self.cells[c] as usize) << 4;
     
         let e = self.get_index(row, east);
-        count += self.cells[e] as u8;
+        config |= (self.cells[e] as usize) << 3;
     
         let sw = self.get_index(south, west);
-        count += self.cells[sw] as u8;
+        config |= (self.cells[sw] as usize) << 2;
     
         let s = self.get_index(south, column);
-        count += self.cells[s] as u8;
+        config |= (self.cells[s] as usize) << 1;
     
         let se = self.get_index(south, east);
-        count += self.cells[se] as u8;
+        config |= self.cells[se] as usize;
     
-        count
+        config
+    }
+
+    /// Like `neighborhood_config`, for the topologies other than the plain
+    /// torus.
+    fn neighborhood_config_across_edges(&self, row: u32, column: u32) -> usize {
+        let mut config = 0;
+        for dr in -1..=1 {
+            for dc in -1..=1 {
+                let alive = self.cell_across_edges(row as i64 + dr as i64, column as i64 + dc as i64);
+                config |= alive as usize * config_bit(dr, dc);
+            }
+        }
+
+        config
+    }
+
+    /// Get the stored value of a cell that may lie beyond the edges.
+    fn cell_across_edges(&self, row: i64, column: i64) -> bool {
+        match self.topology.wrap(row, column, self.width, self.height) {
+            Some((r, c)) => self.cells[self.get_index(r, c)],
+            // There are no cells beyond the edges of a plane, so they stay
+            // dead: the background unless B0 inverted it.
+            None => self.background,
+        }
+    }
+
+    /// Get the cells of a column, which may lie beyond the edges, in the
+    /// rows around a row, as the east column of a 3×3 configuration.
+    fn column_config(&self, row: u32, column: u32) -> usize {
+        if row == 0 || row + 1 >= self.height || column >= self.width {
+            let mut config = 0;
+            for dr in -1..=1 {
+                let alive = self.cell_across_edges(row as i64 + dr as i64, column as i64);
+                config |= alive as usize * config_bit(dr, 1);
+            }
+            return config;
+        }
+
+        let (idx, width) = (self.get_index(row, column), self.width as usize);
+        (self.cells[idx - width] as usize) << 6 | (self.cells[idx] as usize) << 3 | self.cells[idx + width] as usize
+    }
+
+    /// Take the back buffer to compute the next generation into, as large
+    /// as the current one.
+    fn take_back_buffer(&mut self) -> FixedBitSet {
+        let next = std::mem::take(&mut self.back.cells);
+        if next.len() == self.cells.len() {
+            next
+        } else {
+            FixedBitSet::with_capacity(self.cells.len())
+        }
+    }
+
+    /// Make the next generation the current one, keeping the current one as
+    /// the back buffer. Tiles are no longer tracked, see `tick_active`.
+    fn swap_buffers(&mut self, next: FixedBitSet) {
+        self.tiles = None;
+        self.back.cells = std::mem::replace(&mut self.cells, next);
+    }
+
+    /// Get the error of a cell outside of the universe.
+    fn out_of_bounds(&self, row: i64, column: i64) -> Error {
+        Error::OutOfBounds { row, column, width: self.width as u64, height: self.height as u64 }
+    }
+
+    /// Fail if a cell is outside of the universe.
+    fn check_cell(&self, row: i64, column: i64) -> Result<(), Error> {
+        if (0..self.height as i64).contains(&row) && (0..self.width as i64).contains(&column) {
+            Ok(())
+        } else {
+            Err(self.out_of_bounds(row, column))
+        }
+    }
+
+    /// Set the cells of a square pattern, given row by row, around a cell.
+    /// Cells of the pattern beyond the edges are placed as the topology
+    /// says. Fails without setting any cell if the cell is outside of the
+    /// universe, or a cell of the pattern has nowhere to be placed.
+    fn stamp(&mut self, row: u32, column: u32, pattern: &[bool]) -> Result<(), Error> {
+        self.check_cell(row as i64, column as i64)?;
+        let size = (pattern.len() as f64).sqrt() as usize;
+        let half = (size / 2) as i64;
+
+        let mut cells = Vec::with_capacity(pattern.len());
+        for (i, &alive) in pattern.iter().enumerate() {
+            let r = row as i64 - half + (i / size) as i64;
+            let c = column as i64 - half + (i % size) as i64;
+            match self.topology.wrap(r, c, self.width, self.height) {
+                Some((r, c)) => cells.push((self.get_index(r, c), alive)),
+                None => return Err(self.out_of_bounds(r, c)),
+            }
+        }
+
+        for (idx, alive) in cells {
+            self.set_cell(idx, alive)?;
+        }
+        Ok(())
+    }
+
+    /// Make the universe `width` × `height`, moving its cells by a number
+    /// of rows and columns. The cells that end up outside of it are
+    /// removed, and the ones moved into it from nowhere are dead. The cells
+    /// of another engine plugged in all move, and are then shown, or
+    /// nothing changes if the engine cannot hold them where they move.
+    fn reshape(&mut self, width: u32, height: u32, rows: i64, columns: i64) -> Result<(), Error> {
+        if let Some(engine) = &mut self.engine {
+            if rows != 0 || columns != 0 {
+                let live_cells = engine.live_cells();
+                engine.clear();
+                let moved = live_cells.iter().try_for_each(|&(row, column)| engine.set(row + rows, column + columns, true));
+                if let Err(error) = moved {
+                    engine.clear();
+                    for &(row, column) in &live_cells {
+                        engine.set(row, column, true).expect("the cells were in the engine");
+                    }
+                    return Err(error);
+                }
+            }
+        }
+
+        let size = (width * height) as usize;
+        let mut cells = FixedBitSet::with_capacity(size);
+        // Cells are stored relative to the background, so dead cells are
+        // set bits while it is alive.
+        if self.background {
+            cells.insert_range(..);
+        }
+        let mut states = if self.states.is_empty() { Vec::new() } else { vec![Cell::Dead as u8; size] };
+
+        // The columns in both the old and the new universe.
+        let first = (-columns).clamp(0, self.width as i64);
+        let last = (width as i64 - columns).clamp(first, self.width as i64);
+        for row in 0..self.height as i64 {
+            let r = row + rows;
+            if !(0..height as i64).contains(&r) {
+                continue;
+            }
+            for column in first..last {
+                let (from, to) = (self.get_index(row as u32, column as u32), (r * width as i64 + column + columns) as usize);
+                cells.set(to, self.cells[from]);
+                if !states.is_empty() {
+                    states[to] = self.states[from];
+                }
+            }
+        }
+
+        self.width = width;
+        self.height = height;
+        self.cells = cells;
+        self.states = states;
+        self.back = Buffers::default();
+        self.tiles = None;
+        self.show_engine();
+        Ok(())
     }
 }
 
 /// Public methods, exported to JavaScript.
 #[wasm_bindgen]
 impl Universe {
-    pub fn tick(&mut self) {
+    /// Advance the universe by one generation with the algorithm set by
+    /// `set_algorithm`. By default, totalistic rules on the 8 immediate
+    /// neighbours, or the 6 of a hexagonal grid, are computed 64 cells at a
+    /// time, or 128 with the `simd` feature, other rules on them 2×2 cells
+    /// at a time as in `tick_lookup`, and the others cell by cell as in
+    /// `tick_scalar`. With the `parallel` feature, large universes are split
+    /// into bands of rows computed in parallel.
+    ///
+    /// The next generation is computed into a back buffer swapped with the
+    /// current one, so that once the buffers are allocated, rules on the 8
+    /// immediate neighbours, or the 6 of a hexagonal grid, tick without
+    /// allocating.
+    ///
+    /// With another engine plugged in, see `set_backend`, it is that engine
+    /// that advances by one generation instead, which may fail, see `step`.
+    pub fn tick(&mut self) -> Result<(), Error> {
         let _timer = Timer::new("Universe::tick");
 
-        let mut next = self.cells.clone();
+        if self.engine.is_some() {
+            return self.step(1);
+        }
 
-        for row in 0..self.height {
-            for col in 0..self.width {
-                let idx = self.get_index(row, col);
-                let cell = self.cells[idx];
-                let live_neighbors = self.live_neighbor_count(row, col);
+        match self.effective_algorithm() {
+            #[cfg(feature = "parallel")]
+            Algorithm::Bitwise if self.cells.len() >= PARALLEL_CELLS => {
+                self.tick_in_bands(rayon::current_num_threads());
+            }
+            Algorithm::Bitwise => {
+                let mut next = self.take_back_buffer();
+                bitwise::tick(&self.cells, self.width, self.height, self.topology, &self.rule, &mut next, &mut self.back.rows);
+                self.swap_buffers(next);
+            }
+            Algorithm::LookupTable => self.tick_lookup(),
+            Algorithm::ActiveTiles => self.tick_active(),
+            _ => self.tick_scalar(),
+        }
+        Ok(())
+    }
 
-                /*
-                log!(
-                    "cell[{}, {}] is initially {:?} and has {} live neighbors",
-                    row,
-                    col,
-                    if cell == true { Cell::Alive } else { Cell::Dead },
-                    live_neighbors
-                );
-                */
+    /// Advance the universe by a number of generations, or fail if the
+    /// engine holding the cells cannot, having advanced as far as it could.
+    pub fn step(&mut self, generations: u64) -> Result<(), Error> {
+        match &mut self.engine {
+            Some(engine) => {
+                let result = engine.step(generations);
+                self.show_engine();
+                result
+            }
+            None => {
+                for _ in 0..generations {
+                    self.tick()?;
+                }
+                Ok(())
+            }
+        }
+    }
 
-                next.set(idx, match (cell, live_neighbors) {
-                    // Rule 1: Any live cell with fewer than two live neighbours
-                    // dies, as if caused by underpopulation.
-                    (true, x) if x < 2 => false,
-                    // Rule 2: Any live cell with two or three live neighbours
-                    // lives on to the next generation.
-                    (true, 2) | (true, 3) => true,
-                    // Rule 3: Any live cell with more than three live
-                    // neighbours dies, as if by overpopulation.
-                    (true, x) if x > 3 => false,
-                    // Rule 4: Any dead cell with exactly three live neighbours
-                    // becomes a live cell, as if by reproduction.
-                    (false, 3) => true,
-                    // All other cells remain in the same state.
-                    (otherwise, _) => otherwise,
-                });
+    /// Get the name of the engine holding the cells: `dense`, `sparse`,
+    /// `hashlife` or that of another `Engine`.
+    pub fn backend(&self) -> String {
+        self.engine.as_ref().map_or("dense", |engine| engine.name()).to_string()
+    }
 
-                // log!("    it becomes {:?}", if self.cells[idx] == true { Cell::Alive } else { Cell::Dead });
+    /// Set the engine holding the cells from its name, carrying the cells
+    /// over, see `replace_engine`. The dense engine is the universe itself,
+    /// bounded by its edges; `sparse` and `hashlife` are unbounded planes,
+    /// of which the universe shows the rows and columns from 0 to its
+    /// height and width.
+    pub fn set_backend(&mut self, backend: &str) -> Result<(), Error> {
+        let backend: Backend = backend.parse()?;
+        self.replace_engine(backend.create())?;
+        Ok(())
+    }
 
-                /*
-                if cell == true && next[idx] == false {
-                    log!("cell[{}, {}] transitioned Alive to Dead", row, col);
-                } else if cell == false && next[idx] == true {
-                    log!("cell[{}, {}] transitioned Dead to Alive", row, col);
+    /// Get the algorithm that computes the next generation: `auto`,
+    /// `scalar`, `bitwise`, `lookup` or `active`.
+    pub fn algorithm(&self) -> String {
+        self.algorithm.to_string()
+    }
+
+    /// Set the algorithm that computes the next generation from its name,
+    /// see `algorithm`. Every algorithm gives the same generations; one that
+    /// does not support the rule falls back to the fastest one that does.
+    pub fn set_algorithm(&mut self, algorithm: &str) -> Result<(), Error> {
+        self.algorithm = algorithm.parse()?;
+        Ok(())
+    }
+
+    /// Get the number of live cells, up to `u32::MAX`. While the `active`
+    /// algorithm tracks the tiles of the universe, it is kept up to date
+    /// along with them. With another engine plugged in, it counts all of
+    /// its cells, including the ones out of view.
+    pub fn population(&self) -> u32 {
+        if let Some(engine) = &self.engine {
+            return engine.population().min(u32::MAX as u64) as u32;
+        }
+
+        match &self.tiles {
+            Some(tiles) => tiles.population(self.background),
+            None => {
+                let set = self.cells.count_ones(..(self.width * self.height) as usize) as u32;
+                if self.background {
+                    self.width * self.height - set
+                } else {
+                    set
                 }
-                */
             }
         }
+    }
+
+    /// Get the number of cells whose state changed in the last generation,
+    /// or nothing unless it was computed with the `active` algorithm.
+    pub fn changed_cells(&self) -> Option<u32> {
+        self.tiles.as_ref().map(|tiles| tiles.changed_cells())
+    }
 
-        self.cells = next;
+    /// Get the number of tiles of `TILE_SIZE` × `TILE_SIZE` cells that were
+    /// recomputed in the last generation, or nothing unless it was computed
+    /// with the `active` algorithm.
+    pub fn active_tiles(&self) -> Option<u32> {
+        self.tiles.as_ref().map(|tiles| tiles.active())
     }
 
+    /// Create a 64×64 universe where half of the cells are alive, at random,
+    /// see `reset`.
     pub fn new() -> Universe {
         utils::set_panic_hook();
 
-        let width = 64;
-        let height = 64;
+        let mut universe = Universe::empty(64, 64, Rule::default());
+        universe.reset().expect("a soup of an entire universe fits in it");
+        universe
+    }
 
-        let size = (width * height) as usize;
-        let mut cells = FixedBitSet::with_capacity(size);
+    /// Make half of the cells alive at random, from a seed drawn from the
+    /// platform, which `seed` then gets.
+    pub fn reset(&mut self) -> Result<(), Error> {
+        self.reset_with_soup(&Soup::random())
+    }
+
+    /// Fill the universe with a random soup, see `Soup`, returning its seed
+    /// so that the soup can be replayed. Without a seed, one is drawn from
+    /// the platform. The cells are alive with a chance of `density`, within
+    /// a rectangle given as `[top, left, height, width]` or else the entire
+    /// universe, and symmetric as `C1`, `C2`, `C4`, `D2`, `D4` or `D8` say.
+    /// The cells outside the rectangle are dead.
+    pub fn reset_with(
+        &mut self,
+        seed: Option<u64>,
+        density: f64,
+        region: Option<Vec<u32>>,
+        symmetry: Option<String>,
+    ) -> Result<u64, Error> {
+        let region = match region.as_deref() {
+            None => None,
+            Some(&[top, left, height, width]) => Some((top, left, height, width)),
+            Some(region) => {
+                return Err(Error::InvalidArgument(format!(
+                    "expected a soup rectangle of 4 numbers, found {}",
+                    region.len()
+                )));
+            }
+        };
+        let soup = Soup {
+            seed: seed.unwrap_or_else(|| Soup::random().seed),
+            density,
+            region,
+            symmetry: symmetry.as_deref().unwrap_or("C1").parse()?,
+        };
+        self.reset_with_soup(&soup)?;
+        Ok(soup.seed)
+    }
 
-        for i in 0..size {
-            cells.set(i, js_sys::Math::random() < 0.5);
+    /// Get the seed of the last random soup the universe was filled with,
+    /// if any, see `reset_with`.
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    pub fn reset_all_dead(&mut self) {
+        let engine = self.engine.take();
+        self.background = false;
+        for i in 0..(self.width * self.height) as usize {
+            self.show_cell(i, false);
         }
+        self.engine = engine.map(|mut engine| {
+            engine.clear();
+            engine
+        });
+    }
 
-        Universe {
-            width,
-            height,
-            cells,
+    /// Get the rule of the universe in B/S notation.
+    pub fn rule(&self) -> String {
+        self.rule.to_string()
+    }
+
+    /// Get the rule of the universe as a Golly `MAP` string, or nothing if
+    /// the rule looks beyond the 8 immediate neighbours.
+    pub fn rule_map(&self) -> Option<String> {
+        self.rule.to_map_string()
+    }
+
+    /// Set the rule of the universe from a rulestring such as `B36/S23`,
+    /// `S23/B36`, `23/36`, the isotropic rule `B2-a/S12`, a Golly `MAP`
+    /// string, the hexagonal rule `B2/S34H`, the triangular rule `B4/S56L`,
+    /// the Generations rule `B2/S/C3` or the Larger than Life rule
+    /// `R5,C0,M1,S34..58,B34..45,NM`, whose neighbourhood may also be a
+    /// custom one such as the cross `N@213C84`. Live cells are left
+    /// untouched. Fails if the engine holding the cells does not support
+    /// the rule, see `set_backend`.
+    pub fn set_rule(&mut self, rule: &str) -> Result<(), Error> {
+        self.replace_rule(rule.parse()?)?;
+        Ok(())
+    }
+
+    /// Get the edges of the universe as a Golly grid specification, such as
+    /// `T64,64` for a torus or `P64,64` for a bounded plane.
+    pub fn topology(&self) -> String {
+        self.topology.to_spec(self.width, self.height)
+    }
+
+    /// Set the edges of the universe from a Golly grid specification: a
+    /// torus `T64,64`, a torus with shifted edges `T64,64+2`, a bounded
+    /// plane `P64,64`, a Klein bottle `K64*,64`, a cross-surface `C64,64`
+    /// or a sphere `S64`. If the size differs from the current one, the
+    /// universe is resized and all cells reset to the dead state.
+    pub fn set_topology(&mut self, topology: &str) -> Result<(), Error> {
+        let (topology, width, height) = Topology::parse(topology)?;
+        check_size(width, height)?;
+        if (width, height) != (self.width, self.height) {
+            self.reshape(width, height, 0, 0)?;
+            self.reset_all_dead();
         }
+        self.topology = topology;
+        self.tiles = None;
+        self.show_engine();
+        Ok(())
     }
 
-    pub fn reset(&mut self) {
-        for i in 0..(self.width * self.height) as usize {
-            self.cells.set(i, js_sys::Math::random() < 0.5);
+    /// Get the number of cell states of the current rule.
+    pub fn num_states(&self) -> u8 {
+        self.rule.states()
+    }
+
+    /// Returns whether the current rule is hexagonal, so that cells are to
+    /// be drawn as hexagons, see `hex_center_x`.
+    pub fn is_hexagonal(&self) -> bool {
+        self.rule.neighborhood() == Neighborhood::Hexagonal
+    }
+
+    /// Get the horizontal centre of the hexagon of a cell, when drawn with
+    /// hexagons of the given circumradius. Cells are stored in axial
+    /// coordinates, and drawn as a rectangle of pointy-topped hexagons
+    /// where odd rows stick out half a cell to the left.
+    pub fn hex_center_x(&self, row: u32, column: u32, size: f64) -> f64 {
+        hex::center(row, hex::offset_column(row, column, self.width), size).0
+    }
+
+    /// Get the vertical centre of the hexagons of a row, when drawn with
+    /// hexagons of the given circumradius.
+    pub fn hex_center_y(&self, row: u32, size: f64) -> f64 {
+        hex::center(row, 0, size).1
+    }
+
+    /// Get the index of the cell whose hexagon contains a point, when
+    /// drawn with hexagons of the given circumradius, or nothing if the
+    /// point is outside the universe.
+    pub fn hex_cell_at(&self, x: f64, y: f64, size: f64) -> Option<u32> {
+        let (row, offset_column) = hex::cell_at(x, y, size);
+        if !(0..self.height as i64).contains(&row) || !(0..self.width as i64).contains(&offset_column) {
+            return None;
         }
+
+        let row = row as u32;
+        Some(self.get_index(row, hex::axial_column(row, offset_column as u32, self.width)) as u32)
     }
 
-    pub fn reset_all_dead(&mut self) {
-        for i in 0..(self.width * self.height) as usize {
-            self.cells.set(i, false);
+    /// Returns whether the current rule is triangular, so that cells are to
+    /// be drawn as triangles, see `triangle_corners`.
+    pub fn is_triangular(&self) -> bool {
+        self.rule.neighborhood().is_triangular()
+    }
+
+    /// Returns whether the triangle of a cell points up. Triangles point up
+    /// where the sum of the row and column is even, and down elsewhere.
+    pub fn triangle_points_up(&self, row: u32, column: u32) -> bool {
+        triangle::points_up(row, column)
+    }
+
+    /// Get the corners of the triangle of a cell as `[x0, y0, x1, y1, x2,
+    /// y2]`, when drawn with triangles of the given side length. Each row
+    /// alternates between triangles pointing up and down, overlapping
+    /// their neighbours by half a triangle.
+    pub fn triangle_corners(&self, row: u32, column: u32, size: f64) -> Vec<f64> {
+        triangle::corners(row, column, size)
+            .iter()
+            .flat_map(|&(x, y)| [x, y])
+            .collect()
+    }
+
+    /// Get the index of the cell whose triangle contains a point, when
+    /// drawn with triangles of the given side length, or nothing if the
+    /// point is outside the universe.
+    pub fn triangle_cell_at(&self, x: f64, y: f64, size: f64) -> Option<u32> {
+        let (row, column) = triangle::cell_at(x, y, size);
+        if !(0..self.height as i64).contains(&row) || !(0..self.width as i64).contains(&column) {
+            return None;
         }
+
+        Some(self.get_index(row as u32, column as u32) as u32)
     }
 
     pub fn render(&self) -> String {
@@ -219,100 +811,486 @@ impl Universe {
         self.height
     }
 
+    /// Get a pointer to one bit per cell. A set bit is a cell that
+    /// differs from the background, see `background`.
     pub fn cells(&self) -> *const u32 {
         self.cells.as_slice().as_ptr()
     }
 
-    /// Set the width of the universe.
-    /// Resets all cells to the dead state.
-    pub fn set_width(&mut self, width: u32) {
-        self.width = width;
-        for i in 0..(self.width * self.height) as usize { self.cells.set(i, false) }
+    /// Get the effective background: whether the cells outside any pattern
+    /// are alive in this generation. Only rules with B0 make it alive, and
+    /// those without S8 flip it every generation. While it is alive, the
+    /// bits of `cells` are inverted.
+    pub fn background(&self) -> bool {
+        self.background
     }
 
-    /// Set the height of the universe.
-    /// Resets all cells to the dead state.
-    pub fn set_height(&mut self, height: u32) {
-        self.height = height;
-        for i in 0..(self.width * self.height) as usize { self.cells.set(i, false) }
+    /// Get a pointer to one state byte per cell, see `Cell`.
+    /// Only valid while the rule has more than two states.
+    pub fn cell_states(&self) -> *const u8 {
+        self.states.as_ptr()
     }
 
-    pub fn toggle_cell(&mut self, row: u32, column: u32) {
+    /// Set the width of the universe, or fail if the universe cannot have
+    /// that size. Resets all cells to the dead state, see `resize` to keep
+    /// them.
+    pub fn set_width(&mut self, width: u32) -> Result<(), Error> {
+        check_size(width, self.height)?;
+        self.reshape(width, self.height, 0, 0)?;
+        self.reset_all_dead();
+        Ok(())
+    }
+
+    /// Set the height of the universe, or fail if the universe cannot have
+    /// that size. Resets all cells to the dead state, see `resize` to keep
+    /// them.
+    pub fn set_height(&mut self, height: u32) -> Result<(), Error> {
+        check_size(self.width, height)?;
+        self.reshape(self.width, height, 0, 0)?;
+        self.reset_all_dead();
+        Ok(())
+    }
+
+    /// Resize the universe, keeping its cells in place around an anchor:
+    /// `top-left`, `top`, `top-right`, `left`, `center`, `right`,
+    /// `bottom-left`, `bottom` or `bottom-right`. Rows and columns are
+    /// removed or added with dead cells on the other sides of it, see
+    /// `Anchor`. With another engine plugged in, its cells all move with
+    /// the ones in view, and none are removed.
+    pub fn resize(&mut self, width: u32, height: u32, anchor: &str) -> Result<(), Error> {
+        self.resize_anchored(width, height, anchor.parse()?)
+    }
+
+    /// Resize the universe around its live cells, leaving `margin` dead
+    /// rows and columns on every side of them. Fails if every cell is
+    /// dead.
+    pub fn auto_fit(&mut self, margin: u32) -> Result<(), Error> {
+        let (top, left, bottom, right) = match Grid::bounding_box(self) {
+            Some(bounds) => bounds,
+            None => {
+                return Err(Error::InvalidArgument(
+                    "cannot fit a universe around its live cells when every cell is dead".to_string(),
+                ));
+            }
+        };
+
+        let margin = margin as i64;
+        let (width, height) = (right - left + 1 + 2 * margin, bottom - top + 1 + 2 * margin);
+        if width > u32::MAX as i64 || height > u32::MAX as i64 {
+            return Err(Error::InvalidSize(format!(
+                "a {}×{} universe has more than {} cells",
+                width,
+                height,
+                u32::MAX
+            )));
+        }
+        check_size(width as u32, height as u32)?;
+        self.reshape(width as u32, height as u32, margin - top, margin - left)
+    }
+
+    /// Toggle a cell between dead and alive. A cell in a refractory state
+    /// becomes dead. Fails if the cell is outside of the universe.
+    pub fn toggle_cell(&mut self, row: u32, column: u32) -> Result<(), Error> {
+        self.check_cell(row as i64, column as i64)?;
         let idx = self.get_index(row, column);
-        self.cells.set(idx, match self.cells[idx] {
-            true => false,
-            false => true,
-        });
+        let alive = match self.states.get(idx) {
+            Some(&state) => state != Cell::Dead as u8,
+            None => self.cells[idx] != self.background,
+        };
+        self.set_cell(idx, !alive)
+    }
+
+    /// Insert a glider around a cell. Near the edges, the glider is placed
+    /// across them as the topology says. Fails without inserting anything
+    /// if the cell is outside of the universe, or if part of the glider has
+    /// nowhere to go, such as beyond the edges of a plane.
+    pub fn insert_glider(&mut self, row: u32, column: u32) -> Result<(), Error> {
+        self.stamp(row, column, &patterns::GLIDER)
+    }
+
+    /// Insert a pulsar around a cell, see `insert_glider`.
+    pub fn insert_pulsar(&mut self, row: u32, column: u32) -> Result<(), Error> {
+        self.stamp(row, column, &patterns::PULSAR)
     }
+}
 
-    pub fn insert_glider(&mut self, row: u32, column: u32) {
-        if row < 1 || column < 1 || row > self.height - 2 || column > self.width - 2 {
-            return
+impl Universe {
+    /// Advance the universe by one generation, visiting every cell on its
+    /// own. Every rule is supported this way, and it is the reference the
+    /// faster paths of `tick` are checked against. Has no effect while
+    /// another engine holds the cells, see `Universe`.
+    pub fn tick_scalar(&mut self) {
+        if self.engine.is_some() {
+            return;
         }
+        let mut next = self.take_back_buffer();
+        let mut next_states = std::mem::take(&mut self.back.states);
+        next_states.resize(self.states.len(), Cell::Dead as u8);
+
+        // Rules beyond the 8 immediate neighbours count all cells up front,
+        // the others look at the arrangement of the 8 neighbours directly.
+        let mut counts = std::mem::take(&mut self.back.counts);
+        counts.clear();
+        if let Some(kernel) = self.rule.kernel() {
+            neighbors::count_kernel(&self.cells, self.width, self.height, self.topology, kernel, &mut counts);
+        } else if !self.rule.is_life_like() {
+            neighbors::count_range(
+                &self.cells,
+                self.width,
+                self.height,
+                self.topology,
+                self.rule.range(),
+                self.rule.neighborhood(),
+                &mut counts,
+            );
+        }
+
+        for row in 0..self.height {
+            let mut config = 0;
+            for col in 0..self.width {
+                let idx = self.get_index(row, col);
+                let cell = self.cells[idx];
+                let alive = if counts.is_empty() {
+                    // Slide the neighbourhood along the row: its west column
+                    // drops out and the one east of it comes in.
+                    config = if col == 0 {
+                        self.neighborhood_config(row, col)
+                    } else {
+                        (config << 1) & WEST_COLUMNS | self.column_config(row, col + 1)
+                    };
+                    self.rule.next_state_on_background(config, self.background)
+                } else {
+                    let live_neighbors = counts[idx] + (cell && self.rule.include_center()) as u32;
+                    self.rule.next_state(cell, live_neighbors)
+                };
+
+                /*
+                log!(
+                    "cell[{}, {}] is initially {:?} and has neighbourhood {:09b}",
+                    row,
+                    col,
+                    if cell == true { Cell::Alive } else { Cell::Dead },
+                    self.neighborhood_config(row, col)
+                );
+                */
 
-        let pattern = [
-            false, true, false,
-            false, false, true,
-            true, true, true
-        ];
-        let mut pattern_idx = 0;
+                if self.states.is_empty() {
+                    next.set(idx, alive);
+                } else {
+                    let state = self.rule.advance_state(self.states[idx], alive);
+                    next_states[idx] = state;
+                    next.set(idx, state == Cell::Alive as u8);
+                }
+
+                // log!("    it becomes {:?}", if self.cells[idx] == true { Cell::Alive } else { Cell::Dead });
 
-        for i in (row-1)..(row+2) {
-            for j in (column-1)..(column+2) {
-                let idx = self.get_index(i, j);
-                self.cells.set(idx, pattern[pattern_idx]);
-                pattern_idx += 1;
+                /*
+                if cell == true && next[idx] == false {
+                    log!("cell[{}, {}] transitioned Alive to Dead", row, col);
+                } else if cell == false && next[idx] == true {
+                    log!("cell[{}, {}] transitioned Dead to Alive", row, col);
+                }
+                */
             }
         }
+
+        self.back.counts = counts;
+        self.back.states = std::mem::replace(&mut self.states, next_states);
+        self.swap_buffers(next);
+        self.background = self.rule.next_background(self.background);
     }
 
-    pub fn insert_pulsar(&mut self, row: u32, column: u32) {
-        if row < 7 || column < 7 || row > self.height - 8 || column > self.width - 8 {
-            return
-        }
-
-        let pattern = [
-            false, false, false, false, false, false, false, false, false, false, false, false, false, false, false,
-            false, false, false, true, true, true, false, false, false, true, true, true, false, false, false,
-            false, false, false, false, false, false, false, false, false, false, false, false, false, false, false,
-            false, true, false, false, false, false, true, false, true, false, false, false, false, true, false,
-            false, true, false, false, false, false, true, false, true, false, false, false, false, true, false,
-            false, true, false, false, false, false, true, false, true, false, false, false, false, true, false,
-            false, false, false, true, true, true, false, false, false, true, true, true, false, false, false,
-            false, false, false, false, false, false, false, false, false, false, false, false, false, false, false,
-            false, false, false, true, true, true, false, false, false, true, true, true, false, false, false,
-            false, true, false, false, false, false, true, false, true, false, false, false, false, true, false,
-            false, true, false, false, false, false, true, false, true, false, false, false, false, true, false,
-            false, true, false, false, false, false, true, false, true, false, false, false, false, true, false,
-            false, false, false, false, false, false, false, false, false, false, false, false, false, false, false,
-            false, false, false, true, true, true, false, false, false, true, true, true, false, false, false,
-            false, false, false, false, false, false, false, false, false, false, false, false, false, false, false,
-        ];
-        let mut pattern_idx = 0;
-
-        for i in (row-7)..(row+8) {
-            for j in (column-7)..(column+8) {
-                let idx = self.get_index(i, j);
-                self.cells.set(idx, pattern[pattern_idx]);
-                pattern_idx += 1;
-            }
+    /// Advance the universe by one generation 2×2 cells at a time, looking
+    /// up each tile from the 4×4 block around it in a table of the rule.
+    /// The table takes 128 KiB and is computed on the first call after the
+    /// rule changes. Has no effect while another engine holds the cells.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rule is not a rule on the 8 immediate neighbours, or
+    /// the 6 of a hexagonal grid, with two states.
+    pub fn tick_lookup(&mut self) {
+        if self.engine.is_some() {
+            return;
         }
+        let mut next = self.take_back_buffer();
+        let rule = &self.rule;
+        let table = self.lookup.get_or_insert_with(|| lookup::Table::new(rule));
+        table.tick(&self.cells, self.width, self.height, self.topology, self.background, &mut next, &mut self.back.rows);
+        self.swap_buffers(next);
+        self.background = self.rule.next_background(self.background);
     }
-}
 
-impl Universe {
-    /// Get the dead and alive values of the entire universe.
+    /// Advance the universe by one generation like `tick_lookup`, but only
+    /// in the tiles that changed in the last generation and the tiles
+    /// around them; the other tiles are stable. Until the next generation,
+    /// `changed_cells` and `active_tiles` tell what was recomputed. Ticking
+    /// any other way, or changing the rule or the size, stops the tracking,
+    /// and the next call recomputes every tile. Has no effect while another
+    /// engine holds the cells.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rule is not a rule on the 8 immediate neighbours, or
+    /// the 6 of a hexagonal grid, with two states.
+    pub fn tick_active(&mut self) {
+        if self.engine.is_some() {
+            return;
+        }
+        let mut tiles = match self.tiles.take() {
+            Some(tiles) => tiles,
+            None => tiles::Tiles::new(&self.cells, self.width, self.height, self.topology),
+        };
+        let mut next = self.take_back_buffer();
+        let next_background = self.rule.next_background(self.background);
+
+        let rule = &self.rule;
+        let table = self.lookup.get_or_insert_with(|| lookup::Table::new(rule));
+        tiles.tick(table, &self.cells, self.topology, self.background, next_background, &mut next);
+
+        self.swap_buffers(next);
+        self.background = next_background;
+        self.tiles = Some(tiles);
+    }
+
+    /// Advance the universe by one generation like `tick`, splitting the
+    /// rows into about `bands` bands computed in parallel on the rayon
+    /// thread pool. The result is the same for any number of bands. Has no
+    /// effect while another engine holds the cells.
+    ///
+    /// Only totalistic rules on the 8 immediate neighbours, or the 6 of a
+    /// hexagonal grid, with two states and without B0 are split into bands;
+    /// the others are computed as in `tick_scalar`.
+    #[cfg(feature = "parallel")]
+    pub fn tick_in_bands(&mut self, bands: usize) {
+        if self.engine.is_some() {
+            return;
+        }
+        if !bitwise::supports(&self.rule) {
+            return self.tick_scalar();
+        }
+        let mut next = self.take_back_buffer();
+        bitwise::tick_in_bands(&self.cells, self.width, self.height, self.topology, &self.rule, &mut next, bands);
+        self.swap_buffers(next);
+    }
+
+    /// Get the dead and alive values of the entire universe, relative to
+    /// the background: while `background` is alive, set bits are dead cells.
     pub fn get_cells(&self) -> &[u32] {
-        &self.cells.as_slice()
+        self.cells.as_slice()
+    }
+
+    /// Get the state of every cell under a Generations rule.
+    /// Empty if the rule only has two states.
+    pub fn get_cell_states(&self) -> &[u8] {
+        &self.states
+    }
+
+    /// Get the rule of the universe.
+    pub fn get_rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    /// Get how the edges of the universe are joined.
+    pub fn get_topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// Replace how the edges of the universe are joined, returning the
+    /// previous topology. Only square universes can be spheres: on other
+    /// ones, the cells across the edges that have no match are dead.
+    pub fn replace_topology(&mut self, topology: Topology) -> Topology {
+        self.tiles = None;
+        std::mem::replace(&mut self.topology, topology)
+    }
+
+    /// Resize the universe, keeping its cells in place around an anchor,
+    /// see `resize`. Fails if the universe cannot have that size.
+    pub fn resize_anchored(&mut self, width: u32, height: u32, anchor: Anchor) -> Result<(), Error> {
+        check_size(width, height)?;
+        let (rows, columns) = anchor.offset(self.width, self.height, width, height);
+        self.reshape(width, height, rows, columns)
+    }
+
+    /// Get the algorithm that computes the next generation.
+    pub fn get_algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// Replace the algorithm that computes the next generation, returning
+    /// the previous one.
+    pub fn replace_algorithm(&mut self, algorithm: Algorithm) -> Algorithm {
+        std::mem::replace(&mut self.algorithm, algorithm)
+    }
+
+    /// Replace the rule of the universe, returning the previous one. Fails
+    /// without changing anything if the engine holding the cells does not
+    /// support the rule, see `set_rule`.
+    pub fn replace_rule(&mut self, rule: Rule) -> Result<Rule, Error> {
+        if let Some(engine) = &mut self.engine {
+            engine.replace_rule(rule.clone())?;
+        }
+        let previous = std::mem::replace(&mut self.rule, rule);
+        self.sync_states();
+        Ok(previous)
+    }
+
+    /// Fill the universe with a random soup, the cells outside of it dead,
+    /// or fail without changing anything if it does not fit, see
+    /// `Soup::cells`.
+    pub fn reset_with_soup(&mut self, soup: &Soup) -> Result<(), Error> {
+        let cells = soup.cells(self.width, self.height)?;
+        self.reset_all_dead();
+        self.set_cells(&cells)?;
+        self.seed = Some(soup.seed);
+        Ok(())
     }
 
     /// Set cells to be alive in a universe by passing the row and column
-    /// of each cell as an array.
-    pub fn set_cells(&mut self, cells: &[(u32, u32)]) {
+    /// of each cell as an array. Fails without setting any cell if one of
+    /// them is outside of the universe.
+    pub fn set_cells(&mut self, cells: &[(u32, u32)]) -> Result<(), Error> {
+        for &(row, column) in cells {
+            self.check_cell(row as i64, column as i64)?;
+        }
+
         for (row, col) in cells.iter().cloned() {
             let idx = self.get_index(row, col);
-            self.cells.set(idx, true);
+            self.set_cell(idx, true)?;
         }
+        Ok(())
+    }
+}
+
+impl Universe {
+    /// Create a `width` × `height` universe showing the cells of an engine,
+    /// which holds them and computes their generations.
+    pub fn with_engine(width: u32, height: u32, engine: Box<dyn Engine>) -> Universe {
+        let mut universe = Universe::empty(width, height, engine.get_rule().clone());
+        universe.engine = Some(engine);
+        universe.show_engine();
+        universe
+    }
+
+    /// Get the engine holding the cells, unless it is the universe itself.
+    pub fn engine(&self) -> Option<&dyn Engine> {
+        self.engine.as_deref()
+    }
+
+    /// Replace the engine holding the cells, or make the universe hold them
+    /// itself with `None`, returning the previous engine. The live cells
+    /// and the rule are carried over: all of them into another engine, and
+    /// the ones in view into the universe itself. Fails without changing
+    /// anything if the engine does not support the rule. See `Universe` for
+    /// what is left aside while another engine holds the cells.
+    pub fn replace_engine(&mut self, engine: Option<Box<dyn Engine>>) -> Result<Option<Box<dyn Engine>>, Error> {
+        let mut engine = match engine {
+            Some(engine) => engine,
+            None => return Ok(self.engine.take()),
+        };
+
+        engine.clear();
+        engine.replace_rule(self.rule.clone())?;
+        for (row, column) in Grid::live_cells(self) {
+            engine.set(row, column, true)?;
+        }
+
+        let previous = self.engine.replace(engine);
+        self.show_engine();
+        Ok(previous)
+    }
+}
+
+impl Grid for Universe {
+    /// Returns whether a cell is alive. Cells beyond the edges are found
+    /// across them as the topology says, and are dead where there is
+    /// nothing across them.
+    fn get(&self, row: i64, column: i64) -> bool {
+        if let Some(engine) = &self.engine {
+            return engine.get(row, column);
+        }
+
+        match self.topology.wrap(row, column, self.width, self.height) {
+            Some((r, c)) => self.cells[self.get_index(r, c)] != self.background,
+            None => false,
+        }
+    }
+
+    /// Set a cell to be dead or alive. Cells beyond the edges are found
+    /// across them as the topology says, and cannot be set where there is
+    /// nothing across them.
+    fn set(&mut self, row: i64, column: i64, alive: bool) -> Result<(), Error> {
+        let in_view = (0..self.height as i64).contains(&row) && (0..self.width as i64).contains(&column);
+        match &mut self.engine {
+            Some(engine) if !in_view => engine.set(row, column, alive),
+            _ => match self.topology.wrap(row, column, self.width, self.height) {
+                Some((r, c)) => {
+                    let idx = self.get_index(r, c);
+                    self.set_cell(idx, alive)
+                }
+                None => Err(self.out_of_bounds(row, column)),
+            },
+        }
+    }
+
+    fn clear(&mut self) {
+        self.reset_all_dead();
+    }
+
+    fn population(&self) -> u64 {
+        match &self.engine {
+            Some(engine) => engine.population(),
+            None => Universe::population(self) as u64,
+        }
+    }
+
+    fn bounding_box(&self) -> Option<(i64, i64, i64, i64)> {
+        if let Some(engine) = &self.engine {
+            return engine.bounding_box();
+        }
+
+        Grid::live_cells(self).iter().fold(None, |bounds, &(row, column)| {
+            Some(match bounds {
+                Some((t, l, b, r)) => (row.min(t), column.min(l), row.max(b), column.max(r)),
+                None => (row, column, row, column),
+            })
+        })
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64)> {
+        if let Some(engine) = &self.engine {
+            return engine.live_cells();
+        }
+
+        let width = self.width as usize;
+        (0..width * self.height as usize)
+            .filter(|&i| self.cells[i] != self.background)
+            .map(|i| ((i / width) as i64, (i % width) as i64))
+            .collect()
+    }
+}
+
+impl Engine for Universe {
+    fn name(&self) -> &str {
+        self.engine.as_ref().map_or("dense", |engine| engine.name())
+    }
+
+    fn get_rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    fn replace_rule(&mut self, rule: Rule) -> Result<Rule, Error> {
+        Universe::replace_rule(self, rule)
+    }
+
+    fn step(&mut self, generations: u64) -> Result<(), Error> {
+        Universe::step(self, generations)
+    }
+}
+
+impl Default for Universe {
+    fn default() -> Universe {
+        Universe::new()
     }
 }
 
@@ -324,7 +1302,7 @@ impl fmt::Display for Universe {
                 write!(f, "{}", symbol)?;
             }
 
-            write!(f, "\n")?;
+            writeln!(f)?;
         }
 
         Ok(())
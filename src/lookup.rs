@@ -0,0 +1,118 @@
+//! A generation step that advances 2×2 tiles at once, looking up the next
+//! generation of their cells in a table of every 4×4 block, like the leaf
+//! step of Golly's QuickLife.
+//!
+//! The 4×4 block around a tile holds all the neighbours of its 4 cells, so
+//! its 16 cells, as a 16-bit index, decide the tile. The table is computed
+//! from the rule once, for a dead and for an alive background.
+
+use fixedbitset::FixedBitSet;
+
+use crate::bitwise::{extract, read_row, write_bits};
+use crate::rule::{config_bit, Rule};
+use crate::topology::Topology;
+
+/// The number of 4×4 blocks.
+const BLOCKS: usize = 1 << 16;
+
+/// Returns whether a table can be computed for a rule: a rule on the 8
+/// immediate neighbours, or the 6 of a hexagonal grid, with two states.
+pub fn supports(rule: &Rule) -> bool {
+    rule.is_life_like() && !rule.is_generations()
+}
+
+/// The next generation of the middle 2×2 tile of every 4×4 block.
+pub struct Table {
+    // tiles[background * BLOCKS + block]: the tile in the middle of a block
+    // of cells stored relative to a dead (0) or alive (1) background. The
+    // block has row `r` in bits `4r..4r + 4`, west-most first, and the tile
+    // row `r` in bits `2r..2r + 2`.
+    tiles: Box<[u8]>,
+}
+
+impl Table {
+    /// Compute the table of a rule.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rule is not supported, see `supports`.
+    pub fn new(rule: &Rule) -> Table {
+        assert!(supports(rule), "rule '{}' cannot be computed with a lookup table", rule);
+
+        let mut tiles = vec![0u8; 2 * BLOCKS].into_boxed_slice();
+        for (i, tile) in tiles.iter_mut().enumerate() {
+            let (background, block) = (i >= BLOCKS, i % BLOCKS);
+            let alive = |row: i32, column: i32| block >> (4 * row + column) & 1 != 0;
+
+            for (row, column) in [(1, 1), (1, 2), (2, 1), (2, 2)].iter().cloned() {
+                let mut config = 0;
+                for dr in -1..=1 {
+                    for dc in -1..=1 {
+                        if alive(row + dr, column + dc) {
+                            config |= config_bit(dr, dc);
+                        }
+                    }
+                }
+                if rule.next_state_on_background(config, background) {
+                    *tile |= 1 << (2 * (row - 1) + column - 1);
+                }
+            }
+        }
+
+        Table { tiles }
+    }
+
+    /// Get the tiles in the middle of the blocks of cells stored relative
+    /// to a background, indexed by block.
+    pub fn tiles(&self, background: bool) -> &[u8] {
+        &self.tiles[background as usize * BLOCKS..][..BLOCKS]
+    }
+
+    /// Compute the next generation of a `width` × `height` universe into
+    /// `next`, which must be as large as `cells`. The rows being computed
+    /// are kept in `scratch`, which only allocates the first time.
+    #[allow(clippy::too_many_arguments)]
+    pub fn tick(
+        &self,
+        cells: &FixedBitSet,
+        width: u32,
+        height: u32,
+        topology: Topology,
+        background: bool,
+        next: &mut FixedBitSet,
+        scratch: &mut Vec<u64>,
+    ) {
+        let tiles = self.tiles(background);
+        let words = (width as usize).div_ceil(64) + 1;
+
+        // The 4 rows around a row of tiles, and its next generation.
+        scratch.resize(6 * words, 0);
+        let (rows, next_rows) = scratch.split_at_mut(4 * words);
+
+        for top in (0..height as usize).step_by(2) {
+            for (i, row) in rows.chunks_mut(words).enumerate() {
+                read_row(cells, width, height, topology, background, (top + i) as i64 - 1, row);
+            }
+            next_rows.iter_mut().for_each(|word| *word = 0);
+
+            for column in (0..width as usize).step_by(2) {
+                // Bit `column` of a padded row is the cell west of it.
+                let block = rows
+                    .chunks(words)
+                    .enumerate()
+                    .fold(0, |block, (i, row)| block | (extract(row, column) as usize & 0b1111) << (4 * i));
+                let tile = tiles[block] as u64;
+                next_rows[column / 64] |= (tile & 0b11) << (column % 64);
+                next_rows[words + column / 64] |= (tile >> 2 & 0b11) << (column % 64);
+            }
+
+            for (i, next_row) in next_rows.chunks(words).enumerate().take(height as usize - top) {
+                let start = (top + i) * width as usize;
+                for (k, &word) in next_row.iter().enumerate().take(words - 1) {
+                    let len = (width as usize - 64 * k).min(64);
+                    write_bits(next.as_mut_slice(), start + 64 * k, word, len);
+                }
+            }
+        }
+    }
+}
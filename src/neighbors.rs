@@ -0,0 +1,162 @@
+//! Neighbour counts for the range-r neighbourhoods of Larger than Life
+//! rules.
+//!
+//! Counting a radius-10 Moore neighbourhood cell by cell means reading 440
+//! cells for every cell of the universe. Instead, the universe is copied
+//! into a buffer padded by `range` cells on every side, filled in from
+//! across the edges as the topology of the universe says, and the counts
+//! are read off prefix sums of that buffer.
+//!
+//! The neighbourhoods of triangular grids depend on which way each cell
+//! points, and are small enough to be counted cell by cell, as are custom
+//! kernels, whose weights have no prefix sums to take advantage of.
+
+use fixedbitset::FixedBitSet;
+
+use crate::rule::{Kernel, Neighborhood};
+use crate::topology::Topology;
+use crate::triangle;
+
+/// Returns whether the cell at a row and column, which may lie beyond the
+/// edges of the universe, is alive.
+fn is_alive(cells: &FixedBitSet, topology: Topology, row: i64, column: i64, width: u32, height: u32) -> bool {
+    match topology.wrap(row, column, width, height) {
+        Some((row, column)) => cells[(row * width + column) as usize],
+        None => false,
+    }
+}
+
+/// Count the live cells in the neighbourhood of every cell of a
+/// `width` × `height` universe, not including the cell itself. The counts
+/// are written row by row into `counts`. Triangular neighbourhoods ignore
+/// the range.
+pub fn count_range(
+    cells: &FixedBitSet,
+    width: u32,
+    height: u32,
+    topology: Topology,
+    range: u32,
+    neighborhood: Neighborhood,
+    counts: &mut Vec<u32>,
+) {
+    let (w, h, r) = (width as usize, height as usize, range as usize);
+    let padded_width = w + 2 * r;
+    let padded_height = h + 2 * r;
+
+    // The padded row and column `i` are row and column `i - r` of the
+    // universe.
+    let padded: Vec<bool> = (0..padded_height)
+        .flat_map(|y| (0..padded_width).map(move |x| (y, x)))
+        .map(|(y, x)| is_alive(cells, topology, y as i64 - r as i64, x as i64 - r as i64, width, height))
+        .collect();
+
+    counts.clear();
+    counts.reserve(w * h);
+
+    match neighborhood {
+        Neighborhood::Custom => panic!("custom neighbourhoods are counted by count_kernel"),
+        Neighborhood::TriangularEdges | Neighborhood::TriangularVertices => {
+            let offsets: [&[(i32, i32)]; 2] = if neighborhood == Neighborhood::TriangularEdges {
+                [&triangle::EDGE_NEIGHBORS[0], &triangle::EDGE_NEIGHBORS[1]]
+            } else {
+                [&triangle::VERTEX_NEIGHBORS[0], &triangle::VERTEX_NEIGHBORS[1]]
+            };
+
+            for row in 0..height {
+                for col in 0..width {
+                    let up = triangle::points_up(row, col) as usize;
+                    let total = offsets[up]
+                        .iter()
+                        .filter(|&&(dy, dx)| {
+                            is_alive(cells, topology, row as i64 + dy as i64, col as i64 + dx as i64, width, height)
+                        })
+                        .count();
+                    counts.push(total as u32);
+                }
+            }
+        }
+        Neighborhood::Moore => {
+            // sums[y][x] is the number of live cells in the padded rows
+            // above y and the padded columns left of x.
+            let stride = padded_width + 1;
+            let mut sums = vec![0u32; (padded_height + 1) * stride];
+
+            for y in 0..padded_height {
+                let mut row_sum = 0;
+                for x in 0..padded_width {
+                    row_sum += padded[y * padded_width + x] as u32;
+                    sums[(y + 1) * stride + x + 1] = sums[y * stride + x + 1] + row_sum;
+                }
+            }
+
+            let side = 2 * r + 1;
+            for row in 0..h {
+                let (top, bottom) = (row * stride, (row + side) * stride);
+                for col in 0..w {
+                    let (left, right) = (col, col + side);
+                    let total = sums[bottom + right] + sums[top + left] - sums[top + right] - sums[bottom + left];
+                    counts.push(total - cells[row * w + col] as u32);
+                }
+            }
+        }
+        Neighborhood::VonNeumann | Neighborhood::Hexagonal => {
+            // sums[y][x] is the number of live cells in padded row y left
+            // of x. Each row of the diamond or hexagon is then a single
+            // difference.
+            let stride = padded_width + 1;
+            let mut sums = vec![0u32; padded_height * stride];
+
+            for y in 0..padded_height {
+                for x in 0..padded_width {
+                    sums[y * stride + x + 1] = sums[y * stride + x] + padded[y * padded_width + x] as u32;
+                }
+            }
+
+            for row in 0..h {
+                for col in 0..w {
+                    let mut total = 0;
+                    for dy in 0..=2 * r {
+                        // The row spans columns left..=right of the padded
+                        // buffer. In axial coordinates, the hexagon leans
+                        // towards the north-west and the south-east.
+                        let (left, right) = match neighborhood {
+                            Neighborhood::Hexagonal => (dy.saturating_sub(r), (r + dy).min(2 * r)),
+                            _ => (r.abs_diff(dy), r + r - r.abs_diff(dy)),
+                        };
+                        let line = (row + dy) * stride + col;
+                        total += sums[line + right + 1] - sums[line + left];
+                    }
+                    counts.push(total - cells[row * w + col] as u32);
+                }
+            }
+        }
+    }
+}
+
+/// Sum the weights of the live cells in a custom neighbourhood of every
+/// cell of a `width` × `height` universe, including the cell itself if the
+/// kernel weighs it. The sums are written row by row into `counts`.
+pub fn count_kernel(
+    cells: &FixedBitSet,
+    width: u32,
+    height: u32,
+    topology: Topology,
+    kernel: &Kernel,
+    counts: &mut Vec<u32>,
+) {
+    let offsets: Vec<(i32, i32, u32)> = kernel.offsets().collect();
+
+    counts.clear();
+    counts.reserve((width * height) as usize);
+
+    for row in 0..height as i64 {
+        for col in 0..width as i64 {
+            let total = offsets
+                .iter()
+                .filter(|&&(dx, dy, _)| is_alive(cells, topology, row + dy as i64, col + dx as i64, width, height))
+                .map(|&(_, _, weight)| weight)
+                .sum();
+            counts.push(total);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! Patterns that can be stamped into a universe: square arrays of cells,
+//! row by row, centred on the cell they are inserted around, and patterns
+//! read from RLE or plain text, see `parse`.
+
+use crate::error::Error;
+
+/// A glider, heading south-east.
+pub const GLIDER: [bool; 9] = [
+    false, true, false,
+    false, false, true,
+    true, true, true,
+];
+
+/// A pulsar, a period 3 oscillator.
+pub const PULSAR: [bool; 225] = [
+    false, false, false, false, false, false, false, false, false, false, false, false, false, false, false,
+    false, false, false, true, true, true, false, false, false, true, true, true, false, false, false,
+    false, false, false, false, false, false, false, false, false, false, false, false, false, false, false,
+    false, true, false, false, false, false, true, false, true, false, false, false, false, true, false,
+    false, true, false, false, false, false, true, false, true, false, false, false, false, true, false,
+    false, true, false, false, false, false, true, false, true, false, false, false, false, true, false,
+    false, false, false, true, true, true, false, false, false, true, true, true, false, false, false,
+    false, false, false, false, false, false, false, false, false, false, false, false, false, false, false,
+    false, false, false, true, true, true, false, false, false, true, true, true, false, false, false,
+    false, true, false, false, false, false, true, false, true, false, false, false, false, true, false,
+    false, true, false, false, false, false, true, false, true, false, false, false, false, true, false,
+    false, true, false, false, false, false, true, false, true, false, false, false, false, true, false,
+    false, false, false, false, false, false, false, false, false, false, false, false, false, false, false,
+    false, false, false, true, true, true, false, false, false, true, true, true, false, false, false,
+    false, false, false, false, false, false, false, false, false, false, false, false, false, false, false,
+];
+
+/// A pattern read from text, see `parse`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Pattern {
+    pub width: u32,
+    pub height: u32,
+    /// The row and column of every live cell, from the top left corner of
+    /// the pattern, row by row.
+    pub cells: Vec<(u32, u32)>,
+    /// The rule of an RLE header, if it has one.
+    pub rule: Option<String>,
+}
+
+/// Parse a pattern in RLE, such as `x = 3, y = 3, rule = B3/S23` followed
+/// by `bo$2bo$3o!`, or in plain text: rows of `.` for dead and `O` or `*`
+/// for live cells, where lines starting with `!` are comments. The header
+/// of an RLE pattern may be left out.
+///
+/// The pattern must fit in a `width` × `height` universe. This is checked
+/// while the pattern is read, so that a long run of live cells fails
+/// before any of its cells are stored.
+pub fn parse(text: &str, width: u32, height: u32) -> Result<Pattern, Error> {
+    let is_rle = text
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('!'))
+        .is_some_and(|line| line.starts_with('x') || line.contains('$') || line.ends_with('!'));
+    let pattern = if is_rle { parse_rle(text, width, height)? } else { parse_plain_text(text)? };
+    check_fit(pattern.width, pattern.height, width, height)?;
+    Ok(pattern)
+}
+
+/// Fail unless a pattern of some width and height fits in a universe.
+fn check_fit(pattern_width: u32, pattern_height: u32, width: u32, height: u32) -> Result<(), Error> {
+    if pattern_width > width || pattern_height > height {
+        return Err(Error::InvalidArgument(format!(
+            "a {}×{} pattern does not fit in a {}×{} universe",
+            pattern_width, pattern_height, width, height
+        )));
+    }
+    Ok(())
+}
+
+fn parse_rle(text: &str, width: u32, height: u32) -> Result<Pattern, Error> {
+    let mut pattern = Pattern::default();
+    let mut lines = text.lines().map(str::trim).filter(|line| !line.starts_with('#')).peekable();
+
+    if let Some(header) = lines.next_if(|line| line.starts_with('x')) {
+        for field in header.split(',') {
+            let (key, value) = match field.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => return Err(Error::ParseError(format!("invalid RLE header field '{}'", field.trim()))),
+            };
+            let size = || value.parse().map_err(|_| Error::ParseError(format!("invalid RLE pattern size '{}'", value)));
+            match key {
+                "x" => pattern.width = size()?,
+                "y" => pattern.height = size()?,
+                "rule" => pattern.rule = Some(value.to_string()),
+                _ => return Err(Error::ParseError(format!("unknown RLE header field '{}'", key))),
+            }
+        }
+        check_fit(pattern.width, pattern.height, width, height)?;
+    }
+
+    let (mut row, mut column) = (0u32, 0u32);
+    let mut count: Option<u32> = None;
+    'lines: for line in lines {
+        for c in line.chars() {
+            if let Some(digit) = c.to_digit(10) {
+                count = count
+                    .unwrap_or(0)
+                    .checked_mul(10)
+                    .and_then(|count| count.checked_add(digit))
+                    .map(Some)
+                    .ok_or_else(|| Error::ParseError("RLE run count is too large".to_string()))?;
+                continue;
+            }
+
+            let run = count.take().unwrap_or(1);
+            match c {
+                'b' | '.' => column = column.saturating_add(run),
+                'o' | 'A' => {
+                    let end = column.saturating_add(run);
+                    check_fit(end, row.saturating_add(1), width, height)?;
+                    pattern.cells.extend((column..end).map(|c| (row, c)));
+                    column = end;
+                    pattern.width = pattern.width.max(column);
+                }
+                '$' => {
+                    row = row.saturating_add(run);
+                    column = 0;
+                }
+                '!' => break 'lines,
+                c if c.is_whitespace() => {}
+                c => return Err(Error::ParseError(format!("invalid character '{}' in RLE pattern", c))),
+            }
+        }
+    }
+
+    if let Some(&(last, _)) = pattern.cells.last() {
+        pattern.height = pattern.height.max(last + 1);
+    }
+    Ok(pattern)
+}
+
+fn parse_plain_text(text: &str) -> Result<Pattern, Error> {
+    let mut pattern = Pattern::default();
+    for line in text.lines().map(str::trim_end).filter(|line| !line.starts_with('!')) {
+        for (column, c) in line.chars().enumerate() {
+            match c {
+                '.' => {}
+                'O' | '*' => pattern.cells.push((pattern.height, column as u32)),
+                c => return Err(Error::ParseError(format!("invalid character '{}' in plain text pattern", c))),
+            }
+        }
+        pattern.width = pattern.width.max(line.chars().count() as u32);
+        pattern.height += 1;
+    }
+
+    Ok(pattern)
+}
@@ -0,0 +1,101 @@
+//! What the universes need from the platform they run on: random numbers,
+//! timing and logging.
+//!
+//! In WebAssembly, they come from the browser: `Math.random`, and
+//! `console.time` and `console.log`. On other targets, they come from the
+//! standard library, so that the crate can be tested and benchmarked with
+//! plain `cargo test` and `cargo bench`.
+
+pub use self::imp::{log, random, set_timers};
+
+/// Measures the time from its creation until it is dropped, and logs it
+/// under a name. In the browser, this shows as `console.time`. Elsewhere,
+/// nothing is measured unless `set_timers` enabled it.
+pub struct Timer<'a> {
+    name: &'a str,
+    #[cfg(not(target_arch = "wasm32"))]
+    start: Option<std::time::Instant>,
+}
+
+#[cfg(target_arch = "wasm32")]
+mod imp {
+    use web_sys::console;
+
+    use super::Timer;
+
+    /// Get a random number from 0 inclusive to 1 exclusive.
+    pub fn random() -> f64 {
+        js_sys::Math::random()
+    }
+
+    /// Log a message to the console.
+    pub fn log(message: &str) {
+        console::log_1(&message.into());
+    }
+
+    /// Timers always show in the browser's console, so this does nothing.
+    pub fn set_timers(_enabled: bool) {}
+
+    impl<'a> Timer<'a> {
+        pub fn new(name: &'a str) -> Timer<'a> {
+            console::time_with_label(name);
+            Timer { name }
+        }
+    }
+
+    impl<'a> Drop for Timer<'a> {
+        fn drop(&mut self) {
+            console::time_end_with_label(self.name);
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod imp {
+    use std::cell::RefCell;
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Instant;
+
+    use super::Timer;
+    use crate::random::Random;
+
+    static TIMERS: AtomicBool = AtomicBool::new(false);
+
+    thread_local! {
+        // A generator seeded differently in every thread of every process.
+        static RANDOM: RefCell<Random> = RefCell::new(Random::new(RandomState::new().build_hasher().finish()));
+    }
+
+    /// Get a random number from 0 inclusive to 1 exclusive.
+    pub fn random() -> f64 {
+        RANDOM.with(|random| random.borrow_mut().next_f64())
+    }
+
+    /// Log a message to the standard error.
+    pub fn log(message: &str) {
+        eprintln!("{}", message);
+    }
+
+    /// Enable or disable logging the time measured by timers, which is
+    /// disabled by default.
+    pub fn set_timers(enabled: bool) {
+        TIMERS.store(enabled, Ordering::Relaxed);
+    }
+
+    impl<'a> Timer<'a> {
+        pub fn new(name: &'a str) -> Timer<'a> {
+            let start = if TIMERS.load(Ordering::Relaxed) { Some(Instant::now()) } else { None };
+            Timer { name, start }
+        }
+    }
+
+    impl<'a> Drop for Timer<'a> {
+        fn drop(&mut self) {
+            if let Some(start) = self.start {
+                log(&format!("{}: {:?}", self.name, start.elapsed()));
+            }
+        }
+    }
+}
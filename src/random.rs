@@ -0,0 +1,33 @@
+//! A seedable random number generator, so that a random soup can be
+//! replayed from its seed.
+//!
+//! It is SplitMix64, which only uses wrapping 64-bit integer arithmetic, so
+//! a seed gives the same numbers in the browser and on every other target.
+
+/// A SplitMix64 generator.
+#[derive(Clone, Debug)]
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    /// Create a generator from a seed.
+    pub fn new(seed: u64) -> Random {
+        Random { state: seed }
+    }
+
+    /// Get the next 64 random bits.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Get the next random number from 0 inclusive to 1 exclusive, with 53
+    /// random bits.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
@@ -0,0 +1,974 @@
+mod hensel;
+mod kernel;
+mod map;
+
+pub use self::kernel::{Kernel, MAX_WEIGHT};
+
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use crate::error::Error;
+
+/// The largest number of states a Generations rule may have.
+const MAX_STATES: u8 = 255;
+
+/// The largest range of a Larger than Life neighbourhood.
+pub const MAX_RANGE: u32 = 50;
+
+/// The bit of the centre cell in a 3×3 configuration.
+const CENTER: usize = 1 << 4;
+
+/// The bit of the cell at a row and column offset from the centre in a
+/// 3×3 configuration. The north-west cell is the most significant bit and
+/// the south-east cell the least significant one, as in Golly.
+pub fn config_bit(row: i32, column: i32) -> usize {
+    1 << (8 - (row + 1) * 3 - (column + 1))
+}
+
+/// The north-east and south-west cells of a 3×3 configuration, which are
+/// not neighbours on a hexagonal grid.
+const NOT_HEXAGONAL: usize = 1 << 6 | 1 << 2;
+
+/// The number of live neighbours in a 3×3 configuration.
+fn config_count(config: usize) -> usize {
+    (config & !CENTER).count_ones() as usize
+}
+
+/// The number of live hexagonal neighbours in a 3×3 configuration.
+fn hex_config_count(config: usize) -> usize {
+    config_count(config & !NOT_HEXAGONAL)
+}
+
+/// The shape of the neighbourhood a rule counts live cells in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Neighborhood {
+    /// Every cell within the range in both directions, a square.
+    Moore,
+    /// Every cell within the range in Manhattan distance, a diamond.
+    VonNeumann,
+    /// Every cell within the range on a hexagonal grid, a hexagon. The grid
+    /// is stored in axial coordinates, so that the 6 immediate neighbours
+    /// are the 3×3 neighbourhood without the north-east and south-west
+    /// cells.
+    Hexagonal,
+    /// The 3 triangles sharing an edge with a cell of a triangular grid.
+    /// Only has range 1.
+    TriangularEdges,
+    /// The 12 triangles sharing a corner with a cell of a triangular grid.
+    /// Only has range 1.
+    TriangularVertices,
+    /// Any other neighbourhood on the square grid, given by the weights of
+    /// the cells around a cell, see `Rule::kernel`.
+    Custom,
+}
+
+impl Neighborhood {
+    /// The number of cells around the centre within the given range, or
+    /// `None` for `Custom`, whose size depends on its kernel.
+    pub fn size(self, range: u32) -> Option<u32> {
+        match self {
+            Neighborhood::Moore => Some((2 * range + 1) * (2 * range + 1) - 1),
+            Neighborhood::VonNeumann => Some(2 * range * (range + 1)),
+            Neighborhood::Hexagonal => Some(3 * range * (range + 1)),
+            Neighborhood::TriangularEdges => Some(3),
+            Neighborhood::TriangularVertices => Some(12),
+            Neighborhood::Custom => None,
+        }
+    }
+
+    /// Returns whether the neighbourhood is on a triangular grid.
+    pub fn is_triangular(self) -> bool {
+        matches!(self, Neighborhood::TriangularEdges | Neighborhood::TriangularVertices)
+    }
+
+    /// Returns whether a range-1 neighbourhood fits in the 3×3
+    /// neighbourhood of the square grid.
+    fn is_3x3(self) -> bool {
+        matches!(self, Neighborhood::Moore | Neighborhood::Hexagonal)
+    }
+}
+
+/// The rulestring suffixes of the neighbourhoods of other grids than the
+/// square one, longest first.
+const GRID_SUFFIXES: [(&str, Neighborhood); 3] = [
+    ("LE", Neighborhood::TriangularEdges),
+    ("L", Neighborhood::TriangularVertices),
+    ("H", Neighborhood::Hexagonal),
+];
+
+/// An outer-totalistic Life-like rule such as Conway's `B3/S23`, an
+/// isotropic non-totalistic rule such as `B2-a/S12`, a hexagonal rule such
+/// as `B2/S34H`, a triangular rule such as `B4/S56L` or `B1/S12LE`, any
+/// other rule on the 3×3 neighbourhood given as a Golly `MAP` string,
+/// optionally with a Generations suffix such as Brian's Brain `B2/S/C3`,
+/// or a Larger than Life rule such as Bosco's Rule
+/// `R5,C0,M1,S34..58,B34..45,NM`, also with a custom neighbourhood given
+/// as a `Kernel`.
+///
+/// Rules print in the shortest notation that describes them, so two rules
+/// are equal exactly when they print the same.
+///
+/// The rule is stored as a precomputed transition table, so that
+/// `Universe::tick` only has to do a single lookup per cell. Rules on the
+/// 8 immediate neighbours are indexed by the configuration of the 3×3
+/// neighbourhood, see `config_bit`, and the other rules by the current
+/// state of a cell and its number of live neighbours.
+///
+/// In a Generations rule with `n` states, state 0 is dead and state 1 is
+/// alive. A live cell that fails to survive does not die immediately but
+/// passes through the refractory states `2..n`, which neither count as
+/// live neighbours nor can give birth, before becoming dead again.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rule {
+    range: u32,
+    neighborhood: Neighborhood,
+    // Whether a live cell counts towards its own neighbourhood.
+    include_center: bool,
+    // table[0][n]: does a dead cell with n live neighbours become alive?
+    // table[1][n]: does a live cell with n live neighbours stay alive?
+    // Empty if the rule depends on the arrangement of the neighbours.
+    table: [Vec<bool>; 2],
+    // map[config]: is the centre of a 3×3 configuration alive next?
+    // Only present for rules on the 8 immediate neighbours.
+    map: Option<Box<[bool; 512]>>,
+    // background_maps[background][config]: like `map`, for cells stored
+    // relative to a dead (0) or alive (1) background. Only for B0 rules.
+    background_maps: Option<Box<[[bool; 512]; 2]>>,
+    // The weights of the neighbourhood, only for `Neighborhood::Custom`.
+    kernel: Option<Kernel>,
+    states: u8,
+}
+
+impl Rule {
+    /// Conway's Game of Life, `B3/S23`.
+    pub fn conway() -> Rule {
+        Rule::from_counts(&[3], &[2, 3]).expect("B3/S23 is a valid rule")
+    }
+
+    /// Build a rule from the neighbour counts that cause a birth and the
+    /// neighbour counts that let a live cell survive. Fails if any count
+    /// is greater than 8.
+    pub fn from_counts(birth: &[u8], survival: &[u8]) -> Result<Rule, Error> {
+        let table = count_table(birth, survival, Neighborhood::Moore)?;
+        Ok(Rule::life_like(table, Neighborhood::Moore))
+    }
+
+    /// Build a rule on a hexagonal grid from the neighbour counts that
+    /// cause a birth and the neighbour counts that let a live cell survive.
+    /// Fails if any count is greater than 6.
+    pub fn from_hex_counts(birth: &[u8], survival: &[u8]) -> Result<Rule, Error> {
+        let table = count_table(birth, survival, Neighborhood::Hexagonal)?;
+        Ok(Rule::life_like(table, Neighborhood::Hexagonal))
+    }
+
+    /// Build a rule on a triangular grid from the neighbour counts that
+    /// cause a birth and the neighbour counts that let a live cell survive.
+    /// Fails if the neighbourhood is not triangular, if any count is
+    /// greater than the size of the neighbourhood, or if a birth count is 0.
+    pub fn from_triangular_counts(neighborhood: Neighborhood, birth: &[u8], survival: &[u8]) -> Result<Rule, Error> {
+        if !neighborhood.is_triangular() {
+            return Err(Error::InvalidRule(format!("the {:?} neighbourhood is not triangular", neighborhood)));
+        }
+        if birth.contains(&0) {
+            return Err(Error::InvalidRule("triangular rules cannot have B0".to_string()));
+        }
+        let table = count_table(birth, survival, neighborhood)?;
+
+        Ok(Rule {
+            range: 1,
+            neighborhood,
+            include_center: false,
+            table,
+            map: None,
+            background_maps: None,
+            kernel: None,
+            states: 2,
+        })
+    }
+
+    fn life_like(table: [Vec<bool>; 2], neighborhood: Neighborhood) -> Rule {
+        let count = match neighborhood {
+            Neighborhood::Hexagonal => hex_config_count,
+            _ => config_count,
+        };
+
+        let mut map = [false; 512];
+        for (config, next) in map.iter_mut().enumerate() {
+            *next = table[(config & CENTER != 0) as usize][count(config)];
+        }
+
+        Rule::from_map(map)
+    }
+
+    /// Build a rule on the 8 immediate neighbours from the 3×3
+    /// configurations in which the centre cell is alive in the next
+    /// generation. The state of the centre cell in the configurations is
+    /// taken into account, so any rule on the 3×3 neighbourhood can be
+    /// built, whether isotropic or not. Rules that ignore the north-east
+    /// and south-west cells and only count the others are hexagonal.
+    pub fn from_map(map: [bool; 512]) -> Rule {
+        // Keep the counts of totalistic rules so they print as B/S.
+        let (neighborhood, table) = match totalistic_table(&map, 9, config_count) {
+            Some(table) => (Neighborhood::Moore, table),
+            None => match totalistic_table(&map, 7, hex_config_count) {
+                Some(table) => (Neighborhood::Hexagonal, table),
+                None => (Neighborhood::Moore, [Vec::new(), Vec::new()]),
+            },
+        };
+
+        // A rule with B0 turns the whole background alive. Like Golly, keep
+        // the cells relative to the background instead: when the stored
+        // background is alive, cells are stored inverted, and each table
+        // maps stored cells to stored cells. The background of a rule with
+        // B0 but without S8 flips every generation, so the tables alternate.
+        let background_maps = if map[0] {
+            let mut maps = Box::new([[false; 512]; 2]);
+            for (background, stored) in maps.iter_mut().enumerate() {
+                let mask = if background == 0 { 0 } else { 511 };
+                for (config, next) in stored.iter_mut().enumerate() {
+                    *next = map[config ^ mask] != map[mask];
+                }
+            }
+            Some(maps)
+        } else {
+            None
+        };
+
+        Rule {
+            range: 1,
+            neighborhood,
+            include_center: false,
+            table,
+            map: Some(Box::new(map)),
+            background_maps,
+            kernel: None,
+            states: 2,
+        }
+    }
+
+    /// Build a Larger than Life rule. The counts of the birth and survival
+    /// ranges include the cell itself if `include_center` is set.
+    ///
+    /// Fails if the range is 0 or greater than `MAX_RANGE`, if the
+    /// neighbourhood is triangular or custom, or if the counts are not
+    /// valid, see `from_kernel`.
+    pub fn larger_than_life(
+        range: u32,
+        neighborhood: Neighborhood,
+        include_center: bool,
+        survival: RangeInclusive<u32>,
+        birth: RangeInclusive<u32>,
+    ) -> Result<Rule, Error> {
+        if !(1..=MAX_RANGE).contains(&range) {
+            return Err(Error::InvalidRule(format!("range {} is not between 1 and {}", range, MAX_RANGE)));
+        }
+        if neighborhood.is_triangular() || neighborhood == Neighborhood::Custom {
+            return Err(Error::InvalidRule(format!(
+                "Larger than Life rules cannot have a {:?} neighbourhood",
+                neighborhood
+            )));
+        }
+
+        let kernel = match neighborhood {
+            Neighborhood::VonNeumann => Kernel::von_neumann(range),
+            Neighborhood::Hexagonal => Kernel::hexagonal(range),
+            _ => Kernel::moore(range),
+        };
+        let kernel = if include_center {
+            let weights: Vec<(i32, i32, u32)> = kernel.offsets().chain(Some((0, 0, 1))).collect();
+            Kernel::from_weights(&weights)?
+        } else {
+            kernel
+        };
+        check_counts(&kernel, std::slice::from_ref(&birth), std::slice::from_ref(&survival)).map_err(Error::InvalidRule)?;
+
+        let birth: Vec<u32> = birth.collect();
+        let survival: Vec<u32> = survival.collect();
+        Rule::from_kernel(kernel, &birth, &survival)
+    }
+
+    /// Build a totalistic rule on a custom neighbourhood from the weighted
+    /// counts that cause a birth and the weighted counts that let a live
+    /// cell survive. The counts include the cell itself if its weight is
+    /// not 0. Kernels of the other neighbourhoods give the same rule as
+    /// `larger_than_life`.
+    ///
+    /// Fails if there are no birth or no survival counts, which Larger than
+    /// Life notation cannot write, if a count is greater than the total
+    /// weight of the kernel, or if a birth count is 0 beyond the immediate
+    /// neighbours.
+    pub fn from_kernel(kernel: Kernel, birth: &[u32], survival: &[u32]) -> Result<Rule, Error> {
+        let ranges = |counts: &[u32]| counts.iter().map(|&n| n..=n).collect::<Vec<_>>();
+        check_counts(&kernel, &ranges(birth), &ranges(survival)).map_err(Error::InvalidRule)?;
+
+        let center = kernel.weight(0, 0);
+        let around = kernel.without_center();
+        let range = around.range();
+        let standard = [
+            (Neighborhood::Moore, Kernel::moore(range)),
+            (Neighborhood::VonNeumann, Kernel::von_neumann(range)),
+            (Neighborhood::Hexagonal, Kernel::hexagonal(range)),
+        ];
+        let neighborhood = match standard.iter().find(|(_, standard)| *standard == around) {
+            Some(&(neighborhood, _)) if center <= 1 => neighborhood,
+            _ => Neighborhood::Custom,
+        };
+
+        let len = kernel.total_weight() as usize + 1;
+        let mut table = [vec![false; len], vec![false; len]];
+
+        for &n in birth {
+            table[0][n as usize] = true;
+        }
+        for &n in survival {
+            table[1][n as usize] = true;
+        }
+
+        if neighborhood != Neighborhood::Custom {
+            return Ok(Rule::totalistic(range, neighborhood, center == 1, table));
+        }
+
+        Ok(Rule {
+            range: kernel.range(),
+            neighborhood,
+            include_center: false,
+            table,
+            map: None,
+            background_maps: None,
+            kernel: Some(kernel),
+            states: 2,
+        })
+    }
+
+    fn totalistic(range: u32, neighborhood: Neighborhood, include_center: bool, table: [Vec<bool>; 2]) -> Rule {
+        if range == 1 && neighborhood.is_3x3() && !include_center {
+            return Rule::life_like(table, neighborhood);
+        }
+
+        Rule {
+            range,
+            neighborhood,
+            include_center,
+            table,
+            map: None,
+            background_maps: None,
+            kernel: None,
+            states: 2,
+        }
+    }
+
+    /// Turn the rule into a Generations rule with the given number of
+    /// states. Two states gives back the plain Life-like rule. Fails if
+    /// `states` is less than 2, or if the rule has B0 and more than two
+    /// states are asked for.
+    pub fn with_states(self, states: u8) -> Result<Rule, Error> {
+        if states < 2 {
+            return Err(Error::InvalidRule(format!("a rule needs at least two states, not {}", states)));
+        }
+        if states > 2 && self.has_b0() {
+            return Err(Error::InvalidRule(format!("Generations rule '{}/C{}' cannot have B0", self, states)));
+        }
+        Ok(Rule { states, ..self })
+    }
+
+    /// The number of cell states, 2 for a plain Life-like rule.
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    /// Returns whether the rule has refractory states.
+    pub fn is_generations(&self) -> bool {
+        self.states > 2
+    }
+
+    /// The range of the neighbourhood, 1 for a Life-like rule.
+    pub fn range(&self) -> u32 {
+        self.range
+    }
+
+    /// The shape of the neighbourhood.
+    pub fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+
+    /// Returns whether a live cell counts towards its own neighbourhood.
+    /// Custom neighbourhoods weigh the cell itself in their kernel instead.
+    pub fn include_center(&self) -> bool {
+        self.include_center
+    }
+
+    /// The weights of a custom neighbourhood, or `None` for the others.
+    pub fn kernel(&self) -> Option<&Kernel> {
+        self.kernel.as_ref()
+    }
+
+    /// Returns whether the rule only looks at the 8 immediate neighbours,
+    /// or the 6 of a hexagonal grid. Triangular rules do not, since their
+    /// neighbours depend on which way a cell points.
+    pub fn is_life_like(&self) -> bool {
+        self.map.is_some()
+    }
+
+    /// Returns whether the rule only depends on the number of live
+    /// neighbours, not on their arrangement.
+    pub fn is_totalistic(&self) -> bool {
+        !self.table[0].is_empty()
+    }
+
+    /// Returns whether a dead cell without any live neighbours is born,
+    /// which makes the whole background alive.
+    pub fn has_b0(&self) -> bool {
+        match &self.map {
+            Some(map) => map[0],
+            None => self.table[0][0],
+        }
+    }
+
+    /// Returns whether the centre cell of a 3×3 configuration is alive in
+    /// the next generation when cells are stored relative to the given
+    /// background: if `background` is set, stored dead cells are alive and
+    /// stored live cells are dead, both in `config` and in the result. The
+    /// background of the next generation is `next_background(background)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rule does not only look at the 8 immediate neighbours.
+    #[inline]
+    pub fn next_state_on_background(&self, config: usize, background: bool) -> bool {
+        match &self.background_maps {
+            Some(maps) => maps[background as usize][config],
+            None => self.next_state_from_config(config),
+        }
+    }
+
+    /// Returns the state of the background in the next generation. Only
+    /// rules with B0 ever make it alive.
+    pub fn next_background(&self, background: bool) -> bool {
+        match &self.map {
+            Some(map) => map[if background { 511 } else { 0 }],
+            None => false,
+        }
+    }
+
+    /// Returns whether a cell is alive in the next generation. The count
+    /// includes the cell itself if the rule includes the centre.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rule is not totalistic.
+    #[inline]
+    pub fn next_state(&self, alive: bool, live_neighbors: u32) -> bool {
+        self.table[alive as usize][live_neighbors as usize]
+    }
+
+    /// Returns whether the centre cell of a 3×3 configuration, built with
+    /// `config_bit`, is alive in the next generation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rule does not only look at the 8 immediate neighbours.
+    #[inline]
+    pub fn next_state_from_config(&self, config: usize) -> bool {
+        self.map.as_ref().expect("the rule does not use a 3x3 neighbourhood")[config]
+    }
+
+    /// Returns the state of a cell in the next generation, taking the
+    /// refractory states of Generations rules into account.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rule is not totalistic.
+    #[inline]
+    pub fn next_cell_state(&self, state: u8, live_neighbors: u32) -> u8 {
+        self.advance_state(state, state <= 1 && self.next_state(state == 1, live_neighbors))
+    }
+
+    /// Returns the state of a cell in the next generation, given whether
+    /// the transition table makes it alive. Refractory cells of Generations
+    /// rules ignore the table and keep decaying.
+    #[inline]
+    pub fn advance_state(&self, state: u8, alive: bool) -> u8 {
+        match state {
+            0 | 1 if alive => 1,
+            0 => 0,
+            _ if state + 1 < self.states => state + 1,
+            _ => 0,
+        }
+    }
+
+    /// Get the Golly `MAP` string of a rule on the 3×3 neighbourhood, with
+    /// a `/C` suffix for Generations rules. This gives a single notation
+    /// for B/S, Hensel and `MAP` rules, so they can be compared as strings.
+    /// Returns `None` for rules with a larger neighbourhood.
+    pub fn to_map_string(&self) -> Option<String> {
+        let map = self.map.as_ref()?;
+        let mut s = format!("MAP{}", map::encode(map));
+        if self.is_generations() {
+            s.push_str(&format!("/C{}", self.states));
+        }
+
+        Some(s)
+    }
+
+    /// The neighbour counts that cause a birth, in ascending order.
+    /// Empty if the rule is not totalistic.
+    pub fn birth(&self) -> Vec<u32> {
+        counts(&self.table[0])
+    }
+
+    /// The neighbour counts that let a live cell survive, in ascending order.
+    /// Empty if the rule is not totalistic.
+    pub fn survival(&self) -> Vec<u32> {
+        counts(&self.table[1])
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Rule {
+        Rule::conway()
+    }
+}
+
+fn counts(row: &[bool]) -> Vec<u32> {
+    (0..row.len() as u32).filter(|&n| row[n as usize]).collect()
+}
+
+/// Returns the birth and survival tables of a transition table indexed by
+/// 3×3 configurations, if it only depends on the state of the centre and
+/// the given count of live neighbours.
+fn totalistic_table(map: &[bool; 512], len: usize, count: fn(usize) -> usize) -> Option<[Vec<bool>; 2]> {
+    let mut table = [vec![None; len], vec![None; len]];
+    let totalistic = map.iter().enumerate().all(|(config, &next)| {
+        let entry = &mut table[(config & CENTER != 0) as usize][count(config)];
+        *entry.get_or_insert(next) == next
+    });
+
+    if !totalistic {
+        return None;
+    }
+
+    let [birth, survival] = table;
+    Some([
+        birth.into_iter().map(Option::unwrap).collect(),
+        survival.into_iter().map(Option::unwrap).collect(),
+    ])
+}
+
+/// Builds a rule on the 8 immediate neighbours from the neighbour
+/// configurations, without the centre, that cause a birth or survival.
+fn from_configs(birth: &[usize], survival: &[usize]) -> Rule {
+    let mut map = [false; 512];
+    for &config in birth {
+        map[config] = true;
+    }
+    for &config in survival {
+        map[config | CENTER] = true;
+    }
+
+    Rule::from_map(map)
+}
+
+fn parse_states(states: &str) -> Result<u8, String> {
+    match states.parse::<u8>() {
+        Ok(n) if n >= 2 => Ok(n),
+        _ => Err(format!("invalid number of states '{}', expected 2 to {}", states, MAX_STATES)),
+    }
+}
+
+fn with_states(rule: Rule, states: u8) -> Result<Rule, String> {
+    rule.with_states(states).map_err(|error| error.to_string())
+}
+
+fn parse_number(s: &str) -> Result<u32, String> {
+    s.parse().map_err(|_| format!("invalid number '{}'", s))
+}
+
+/// Parses a count range such as `34..58`, or a single count such as `3`.
+fn parse_count_range(s: &str) -> Result<RangeInclusive<u32>, String> {
+    let (min, max) = match s.find("..") {
+        Some(i) => (parse_number(&s[..i])?, parse_number(&s[i + 2..])?),
+        None => (parse_number(s)?, parse_number(s)?),
+    };
+
+    if min > max {
+        return Err(format!("count range '{}' is empty", s));
+    }
+
+    Ok(min..=max)
+}
+
+/// The neighbourhood part of Larger than Life notation, which can only be
+/// turned into a kernel once the range is known.
+enum Shape<'a> {
+    Standard(Neighborhood),
+    Mask(&'a str),
+    Weights(&'a str),
+}
+
+/// Parses Larger than Life notation such as `R5,C0,M1,S34..58,B34..45,NM`.
+/// `C`, `M` and `N` default to 2 states, excluding the centre and the
+/// Moore neighbourhood. Besides `NM`, `NN` and `NH`, the neighbourhood may
+/// be a custom kernel in LifeViewer's `N@` or `NW` notation, see `Kernel`.
+/// Non-contiguous counts list several ranges, as in `S2..3,5..6`.
+fn parse_larger_than_life(s: &str) -> Result<Rule, String> {
+    let mut range = None;
+    let mut states = 2;
+    let mut include_center = false;
+    let mut survival: Option<Vec<RangeInclusive<u32>>> = None;
+    let mut birth: Option<Vec<RangeInclusive<u32>>> = None;
+    let mut shape = Shape::Standard(Neighborhood::Moore);
+    // The counts that a part starting with a digit continues.
+    let mut last_counts = None;
+
+    for part in s.split(',').map(str::trim) {
+        let mut chars = part.chars();
+        let key = chars.next().map(|c| c.to_ascii_uppercase());
+        let value = chars.as_str();
+
+        match key {
+            Some('R') => range = Some(parse_number(value)?),
+            // Larger than Life writes both C0 and C1 for two states.
+            Some('C') => states = if value == "0" || value == "1" { 2 } else { parse_states(value)? },
+            Some('M') => include_center = match value {
+                "0" => false,
+                "1" => true,
+                _ => return Err(format!("invalid middle '{}', expected M0 or M1", part)),
+            },
+            Some('S') => survival = Some(vec![parse_count_range(value)?]),
+            Some('B') => birth = Some(vec![parse_count_range(value)?]),
+            Some('N') => shape = match value {
+                "M" | "m" => Shape::Standard(Neighborhood::Moore),
+                "N" | "n" => Shape::Standard(Neighborhood::VonNeumann),
+                "H" | "h" => Shape::Standard(Neighborhood::Hexagonal),
+                _ if value.starts_with('@') => Shape::Mask(&value[1..]),
+                _ if value.starts_with(['W', 'w']) => Shape::Weights(&value[1..]),
+                _ => return Err(format!("invalid neighbourhood '{}', expected NM, NN, NH, N@ or NW", part)),
+            },
+            Some(c) if c.is_ascii_digit() => match last_counts {
+                Some('S') => survival.get_or_insert_with(Vec::new).push(parse_count_range(part)?),
+                Some('B') => birth.get_or_insert_with(Vec::new).push(parse_count_range(part)?),
+                _ => return Err(format!("counts '{}' do not follow S or B", part)),
+            },
+            _ => return Err(format!("invalid Larger than Life part '{}'", part)),
+        }
+
+        if key.is_some_and(|c| !c.is_ascii_digit()) {
+            last_counts = key;
+        }
+    }
+
+    let (range, survival, birth) = match (range, survival, birth) {
+        (Some(range), Some(survival), Some(birth)) => (range, survival, birth),
+        _ => return Err(format!("rule '{}' needs a range and survival and birth counts", s)),
+    };
+
+    if !(1..=MAX_RANGE).contains(&range) {
+        return Err(format!("range {} is not between 1 and {}", range, MAX_RANGE));
+    }
+
+    let kernel = match shape {
+        Shape::Standard(Neighborhood::VonNeumann) => Kernel::von_neumann(range),
+        Shape::Standard(Neighborhood::Hexagonal) => Kernel::hexagonal(range),
+        Shape::Standard(_) => Kernel::moore(range),
+        Shape::Mask(hex) => Kernel::from_hex_mask(range, hex).map_err(|error| error.to_string())?,
+        Shape::Weights(hex) => Kernel::from_hex_weights(range, hex).map_err(|error| error.to_string())?,
+    };
+    let kernel = if include_center {
+        if kernel.weight(0, 0) == MAX_WEIGHT {
+            return Err(format!("rule '{}' weighs the middle cell more than {}", s, MAX_WEIGHT));
+        }
+        let weights: Vec<(i32, i32, u32)> = kernel.offsets().chain(Some((0, 0, 1))).collect();
+        Kernel::from_weights(&weights).map_err(|error| error.to_string())?
+    } else {
+        kernel
+    };
+
+    // Check the ranges before listing their counts, which may be many.
+    check_counts(&kernel, &birth, &survival).map_err(|error| format!("rule '{}': {}", s, error))?;
+    let survival: Vec<u32> = survival.into_iter().flatten().collect();
+    let birth: Vec<u32> = birth.into_iter().flatten().collect();
+    let rule = Rule::from_kernel(kernel, &birth, &survival).map_err(|error| error.to_string())?;
+
+    with_states(rule, states)
+}
+
+/// Checks the birth and survival count ranges of a totalistic rule on a
+/// kernel: there must be some of each, none of them empty or above the
+/// total weight of the kernel, and B0 only on the immediate neighbours,
+/// the only rules with B0 that a universe can emulate.
+fn check_counts(kernel: &Kernel, birth: &[RangeInclusive<u32>], survival: &[RangeInclusive<u32>]) -> Result<(), String> {
+    let max = kernel.total_weight();
+    for (name, ranges) in [("birth", birth), ("survival", survival)].iter() {
+        if ranges.is_empty() {
+            return Err(format!("the rule has no {} counts", name));
+        }
+        for counts in ranges.iter() {
+            if counts.is_empty() {
+                return Err(format!("{} range {}..{} is empty", name, counts.start(), counts.end()));
+            }
+            if *counts.end() > max {
+                return Err(format!(
+                    "{} range {}..{} goes beyond the neighbourhood size {}",
+                    name,
+                    counts.start(),
+                    counts.end(),
+                    max
+                ));
+            }
+        }
+    }
+
+    let immediate = kernel.weight(0, 0) == 0 && (*kernel == Kernel::moore(1) || *kernel == Kernel::hexagonal(1));
+    if !immediate && birth.iter().any(|counts| counts.contains(&0)) {
+        return Err("B0 is only supported on the immediate neighbours".to_string());
+    }
+
+    Ok(())
+}
+
+/// Lays out the birth and survival counts of a totalistic rule on a range-1
+/// neighbourhood as a table indexed by the count, failing if a count is
+/// greater than the size of the neighbourhood.
+fn count_table(birth: &[u8], survival: &[u8], neighborhood: Neighborhood) -> Result<[Vec<bool>; 2], Error> {
+    let size = neighborhood.size(1).expect("range-1 rules have a standard neighbourhood") as usize;
+    let mut table = [vec![false; size + 1], vec![false; size + 1]];
+
+    for (outcomes, counts) in table.iter_mut().zip([birth, survival].iter()) {
+        for &n in counts.iter() {
+            match outcomes.get_mut(n as usize) {
+                Some(outcome) => *outcome = true,
+                None => {
+                    return Err(Error::InvalidRule(format!(
+                        "count {} goes beyond the neighbourhood size {}",
+                        n, size
+                    )));
+                }
+            }
+        }
+    }
+
+    Ok(table)
+}
+
+/// Parses a Golly `MAP` string, with an optional Generations suffix `/C3`
+/// or `/3` after the 86 base64 characters.
+fn parse_map(s: &str) -> Result<Rule, String> {
+    let payload = &s[3..];
+    let len = if payload.get(map::LEN..map::LEN + 2) == Some("==") { map::LEN + 2 } else { map::LEN };
+    let (table, suffix) = match (payload.get(..len), payload.get(len..)) {
+        (Some(table), Some(suffix)) => (table, suffix),
+        _ => return Err(format!("MAP rule '{}' is too short", s)),
+    };
+
+    let states = match suffix.strip_prefix('/') {
+        None if suffix.is_empty() => 2,
+        None => return Err(format!("MAP rule '{}' has trailing characters '{}'", s, suffix)),
+        Some(states) => parse_states(states.strip_prefix(['C', 'c']).unwrap_or(states))?,
+    };
+
+    with_states(Rule::from_map(map::decode(table)?), states)
+}
+
+/// Parses `B36/S23`, `S23/B36` and MCell-style `23/36` (survival/birth)
+/// notation, followed by an optional Generations suffix: `/C3` after B/S
+/// parts, or `/3` after MCell parts. Letters are case-insensitive. A
+/// trailing `H` on the second or last part, as in `B2/S34H`, makes the
+/// rule hexagonal, with neighbour counts up to 6. A trailing `L` makes it
+/// triangular with the 12 triangles sharing a corner, and `LE` with the 3
+/// sharing an edge, with neighbour counts written in hexadecimal up to `C`.
+///
+/// Rules starting with `R` and a range, such as
+/// `R5,C0,M1,S34..58,B34..45,NM`, are parsed as Larger than Life rules,
+/// and rules starting with `MAP` as Golly `MAP` strings.
+impl FromStr for Rule {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Rule, Error> {
+        parse_rule(s).map_err(Error::InvalidRule)
+    }
+}
+
+/// Parses a rule in any of the notations of `Rule::from_str`.
+fn parse_rule(s: &str) -> Result<Rule, String> {
+    let s = s.trim();
+    if s.starts_with(['R', 'r']) && s.contains(',') {
+        return parse_larger_than_life(s);
+    }
+    if s.get(..3).is_some_and(|prefix| prefix.eq_ignore_ascii_case("MAP")) {
+        return parse_map(s);
+    }
+
+    let mut parts: Vec<&str> = s.split('/').map(str::trim).collect();
+    let mut neighborhood = Neighborhood::Moore;
+    for part in parts.iter_mut().skip(1) {
+        for &(suffix, grid) in GRID_SUFFIXES.iter() {
+            let split = part.len().checked_sub(suffix.len()).filter(|&i| part.is_char_boundary(i));
+            if let Some(i) = split.filter(|&i| part[i..].eq_ignore_ascii_case(suffix)) {
+                neighborhood = grid;
+                *part = &part[..i];
+                break;
+            }
+        }
+    }
+
+    let (first, second, third) = match parts[..] {
+        [first, second] => (first, second, None),
+        [first, second, third] => (first, second, Some(third)),
+        _ => return Err(format!("rule '{}' must have two or three parts separated by '/'", s)),
+    };
+
+    // Rules on the square and hexagonal grids are parsed into the 3×3
+    // configurations they allow, triangular rules into counts.
+    let conditions = |part: &str| match neighborhood {
+        Neighborhood::Hexagonal => Ok(parse_counts(part, 6)?
+            .into_iter()
+            .flat_map(|count| (0..512).filter(move |&config| config & CENTER == 0 && hex_config_count(config) == count))
+            .collect()),
+        Neighborhood::TriangularEdges | Neighborhood::TriangularVertices => {
+            parse_counts(part, neighborhood.size(1).expect("triangular neighbourhoods have a size"))
+        }
+        _ => hensel::parse(part),
+    };
+
+    let mut birth = None;
+    let mut survival = None;
+
+    for part in [first, second].iter() {
+        let mut chars = part.chars();
+        match chars.next().map(|c| c.to_ascii_uppercase()) {
+            Some('B') if birth.is_none() => birth = Some(conditions(chars.as_str())?),
+            Some('S') if survival.is_none() => survival = Some(conditions(chars.as_str())?),
+            Some('B') | Some('S') => return Err(format!("rule '{}' repeats a part", s)),
+            _ => {}
+        }
+    }
+
+    let (birth, survival, states) = match (birth, survival, third) {
+        (Some(birth), Some(survival), None) => (birth, survival, 2),
+        (Some(birth), Some(survival), Some(third)) => match third.chars().next() {
+            Some('C') | Some('c') => (birth, survival, parse_states(&third[1..])?),
+            _ => return Err(format!("rule '{}' has an invalid Generations suffix '{}'", s, third)),
+        },
+        (None, None, third) => (
+            conditions(second)?,
+            conditions(first)?,
+            third.map_or(Ok(2), parse_states)?,
+        ),
+        _ => return Err(format!("rule '{}' mixes B/S and MCell notation", s)),
+    };
+
+    if !neighborhood.is_triangular() {
+        return with_states(from_configs(&birth, &survival), states);
+    }
+
+    if birth.contains(&0) {
+        return Err(format!("triangular rule '{}' cannot have B0", s));
+    }
+
+    let birth: Vec<u8> = birth.into_iter().map(|n| n as u8).collect();
+    let survival: Vec<u8> = survival.into_iter().map(|n| n as u8).collect();
+    let rule = Rule::from_triangular_counts(neighborhood, &birth, &survival).map_err(|error| error.to_string())?;
+    with_states(rule, states)
+}
+
+/// Parses the neighbour counts of one part of a totalistic rule on another
+/// grid than the square one, such as `34`. Counts above 9 are written in
+/// hexadecimal.
+fn parse_counts(conditions: &str, max: u32) -> Result<Vec<usize>, String> {
+    conditions
+        .chars()
+        .map(|c| match c.to_digit(16) {
+            Some(count) if count <= max => Ok(count as usize),
+            _ => Err(format!("invalid neighbour count '{}', expected 0 to {:X}", c, max)),
+        })
+        .collect()
+}
+
+/// Writes the counts of a Larger than Life rule as ranges. There is always
+/// at least one count, see `Rule::from_kernel`.
+fn write_count_range(f: &mut fmt::Formatter, counts: &[u32]) -> fmt::Result {
+    // Split the counts into contiguous runs, written as separate ranges.
+    let mut start = 0;
+    for i in 1..=counts.len() {
+        if i == counts.len() || counts[i] != counts[i - 1] + 1 {
+            let separator = if start == 0 { "" } else { "," };
+            write!(f, "{}{}..{}", separator, counts[start], counts[i - 1])?;
+            start = i;
+        }
+    }
+
+    Ok(())
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.map {
+            Some(map) if !self.is_totalistic() && !hensel::is_isotropic(map) => {
+                write!(f, "MAP{}", map::encode(map))?;
+            }
+            Some(map) if !self.is_totalistic() => {
+                write!(f, "B{}", hensel::format(|config| map[config]))?;
+                write!(f, "/S{}", hensel::format(|config| map[config | CENTER]))?;
+            }
+            Some(_) => {
+                write!(f, "B")?;
+                for n in self.birth() {
+                    write!(f, "{}", n)?;
+                }
+
+                write!(f, "/S")?;
+                for n in self.survival() {
+                    write!(f, "{}", n)?;
+                }
+
+                if self.neighborhood == Neighborhood::Hexagonal {
+                    write!(f, "H")?;
+                }
+            }
+            None if self.neighborhood.is_triangular() => {
+                write!(f, "B")?;
+                for n in self.birth() {
+                    write!(f, "{:X}", n)?;
+                }
+
+                write!(f, "/S")?;
+                for n in self.survival() {
+                    write!(f, "{:X}", n)?;
+                }
+
+                let suffix = GRID_SUFFIXES.iter().find(|&&(_, grid)| grid == self.neighborhood);
+                write!(f, "{}", suffix.map_or("", |&(suffix, _)| suffix))?;
+            }
+            None => {
+                // Kernels with weights other than 1 weigh the middle cell
+                // themselves, the others leave it to M.
+                let include_center = match &self.kernel {
+                    Some(kernel) => kernel.is_unweighted() && kernel.weight(0, 0) == 1,
+                    None => self.include_center,
+                };
+                let states = if self.is_generations() { self.states } else { 0 };
+                write!(f, "R{},C{},M{},S", self.range, states, include_center as u8)?;
+                write_count_range(f, &self.survival())?;
+                write!(f, ",B")?;
+                write_count_range(f, &self.birth())?;
+                return match (self.neighborhood, &self.kernel) {
+                    (Neighborhood::Moore, _) => write!(f, ",NM"),
+                    (Neighborhood::VonNeumann, _) => write!(f, ",NN"),
+                    (Neighborhood::Hexagonal, _) => write!(f, ",NH"),
+                    (_, Some(kernel)) if kernel.is_unweighted() => {
+                        write!(f, ",N@{}", kernel.without_center().to_hex_mask())
+                    }
+                    (_, Some(kernel)) => write!(f, ",NW{}", kernel.to_hex_weights()),
+                    (_, None) => unreachable!(),
+                };
+            }
+        }
+
+        if self.is_generations() {
+            write!(f, "/C{}", self.states)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,219 @@
+//! Hensel notation for isotropic non-totalistic rules, such as `B2-a/S12`.
+//!
+//! Each neighbour count from 1 to 7 is split into the ways its live
+//! neighbours can be arranged up to rotation and reflection, and every
+//! arrangement is named by a letter. Counts 5 to 7 reuse the letters of
+//! counts 3 to 1 for the complementary arrangements.
+
+use super::{config_bit, CENTER};
+
+type Offset = (i32, i32);
+
+const NW: Offset = (-1, -1);
+const N: Offset = (-1, 0);
+const NE: Offset = (-1, 1);
+const W: Offset = (0, -1);
+const E: Offset = (0, 1);
+const SW: Offset = (1, -1);
+const S: Offset = (1, 0);
+const SE: Offset = (1, 1);
+
+/// One arrangement of each letter of the counts 1 to 4.
+const LETTERS: [(u32, char, &[Offset]); 31] = [
+    (1, 'c', &[NE]),
+    (1, 'e', &[N]),
+    (2, 'a', &[N, NE]),
+    (2, 'c', &[NE, SE]),
+    (2, 'e', &[N, E]),
+    (2, 'i', &[N, S]),
+    (2, 'k', &[N, SE]),
+    (2, 'n', &[NE, SW]),
+    (3, 'a', &[N, NE, E]),
+    (3, 'c', &[NE, SE, SW]),
+    (3, 'e', &[N, E, S]),
+    (3, 'i', &[NW, N, NE]),
+    (3, 'j', &[N, NE, W]),
+    (3, 'k', &[N, E, SW]),
+    (3, 'n', &[N, NE, SE]),
+    (3, 'q', &[N, NE, SW]),
+    (3, 'r', &[N, NE, S]),
+    (3, 'y', &[N, SE, SW]),
+    (4, 'a', &[N, NE, E, SE]),
+    (4, 'c', &[NW, NE, SE, SW]),
+    (4, 'e', &[N, E, S, W]),
+    (4, 'i', &[N, NE, SE, S]),
+    (4, 'j', &[N, NE, S, W]),
+    (4, 'k', &[N, NE, SE, W]),
+    (4, 'n', &[NW, N, NE, SE]),
+    (4, 'q', &[N, NE, E, SW]),
+    (4, 'r', &[N, NE, E, S]),
+    (4, 't', &[NW, N, NE, S]),
+    (4, 'w', &[N, NE, SW, W]),
+    (4, 'y', &[N, NE, SE, SW]),
+    (4, 'z', &[N, NE, S, SW]),
+];
+
+/// The 8 rotations and reflections of the square.
+const SYMMETRIES: [fn(Offset) -> Offset; 8] = [
+    |(r, c)| (r, c),
+    |(r, c)| (c, -r),
+    |(r, c)| (-r, -c),
+    |(r, c)| (-c, r),
+    |(r, c)| (r, -c),
+    |(r, c)| (-r, c),
+    |(r, c)| (c, r),
+    |(r, c)| (-c, -r),
+];
+
+// All the neighbours, without the centre bit.
+const NEIGHBORS: usize = 0x1ff & !CENTER;
+
+/// The letters of a neighbour count, in alphabetical order.
+pub fn letters(count: u32) -> Vec<char> {
+    let count = if count > 4 { 8 - count } else { count };
+    LETTERS.iter().filter(|l| l.0 == count).map(|l| l.1).collect()
+}
+
+/// The arrangements of live neighbours named by a count and a letter, as
+/// configurations of the 3×3 neighbourhood without the centre.
+pub fn configs(count: u32, letter: char) -> Vec<usize> {
+    let complement = count > 4;
+    let base = if complement { 8 - count } else { count };
+
+    let mut configs = Vec::new();
+    for &(_, _, offsets) in LETTERS.iter().filter(|l| l.0 == base && l.1 == letter) {
+        for symmetry in SYMMETRIES.iter() {
+            let mut config = offsets.iter().fold(0, |config, &offset| {
+                let (r, c) = symmetry(offset);
+                config | config_bit(r, c)
+            });
+            if complement {
+                config ^= NEIGHBORS;
+            }
+            if !configs.contains(&config) {
+                configs.push(config);
+            }
+        }
+    }
+
+    configs
+}
+
+/// Applies a rotation or reflection to a 3×3 configuration.
+fn transform(config: usize, symmetry: fn(Offset) -> Offset) -> usize {
+    let mut transformed = 0;
+    for r in -1..=1 {
+        for c in -1..=1 {
+            if config & config_bit(r, c) != 0 {
+                let (r, c) = symmetry((r, c));
+                transformed |= config_bit(r, c);
+            }
+        }
+    }
+
+    transformed
+}
+
+/// Returns whether a transition table indexed by 3×3 configurations gives
+/// the same result for every rotation and reflection of a configuration,
+/// so that it can be written in Hensel notation.
+pub fn is_isotropic(map: &[bool; 512]) -> bool {
+    (0..512).all(|config| {
+        SYMMETRIES
+            .iter()
+            .all(|&symmetry| map[transform(config, symmetry)] == map[config])
+    })
+}
+
+/// Parses the conditions of one part of a rule, such as `2-a` or `2ik3`,
+/// into the set of neighbour configurations they allow.
+pub fn parse(conditions: &str) -> Result<Vec<usize>, String> {
+    let mut allowed = Vec::new();
+    let mut chars = conditions.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let count = match c.to_digit(10) {
+            Some(count) if count <= 8 => count,
+            _ => return Err(format!("invalid neighbour count '{}'", c)),
+        };
+
+        let negate = chars.peek() == Some(&'-');
+        if negate {
+            chars.next();
+        }
+
+        let mut named = Vec::new();
+        while let Some(&letter) = chars.peek() {
+            if !letter.is_ascii_alphabetic() {
+                break;
+            }
+            chars.next();
+
+            let letter = letter.to_ascii_lowercase();
+            if !letters(count).contains(&letter) {
+                return Err(format!("invalid letter '{}' for neighbour count {}", letter, count));
+            }
+            named.push(letter);
+        }
+
+        if negate && named.is_empty() {
+            return Err(format!("neighbour count {} has '-' without letters", count));
+        }
+
+        if count == 0 || count == 8 {
+            allowed.push(if count == 0 { 0 } else { NEIGHBORS });
+            continue;
+        }
+
+        for letter in letters(count) {
+            if named.is_empty() || named.contains(&letter) != negate {
+                allowed.extend(configs(count, letter));
+            }
+        }
+    }
+
+    Ok(allowed)
+}
+
+/// Writes the conditions of one part of a rule given the neighbour
+/// configurations it allows. A count is written with the letters it
+/// allows, or with `-` and the letters it does not allow if that is
+/// shorter.
+pub fn format(allowed: impl Fn(usize) -> bool) -> String {
+    let mut s = String::new();
+
+    for count in 0..=8 {
+        if count == 0 || count == 8 {
+            let config = if count == 0 { 0 } else { NEIGHBORS };
+            if allowed(config) {
+                s.push_str(&count.to_string());
+            }
+            continue;
+        }
+
+        let all = letters(count);
+        let present: Vec<char> = all
+            .iter()
+            .cloned()
+            .filter(|&letter| configs(count, letter).into_iter().all(&allowed))
+            .collect();
+
+        if present.is_empty() {
+            continue;
+        }
+
+        s.push_str(&count.to_string());
+        if present.len() == all.len() {
+            continue;
+        }
+
+        if present.len() > all.len() - present.len() {
+            s.push('-');
+            s.extend(all.iter().filter(|letter| !present.contains(letter)));
+        } else {
+            s.extend(present.iter());
+        }
+    }
+
+    s
+}
@@ -0,0 +1,271 @@
+//! Custom neighbourhoods, given as weighted offsets around a cell.
+
+use super::MAX_RANGE;
+use crate::error::Error;
+
+/// The largest weight of a cell in a kernel, so that weights can be
+/// written as single hexadecimal digits like in LifeViewer's `NW` notation.
+pub const MAX_WEIGHT: u32 = 15;
+
+/// A custom neighbourhood: the weight of every cell within a square of
+/// side `2 * range + 1` around a cell. A cell with weight 0 is not part of
+/// the neighbourhood, and a cell with weight 2 counts as two live
+/// neighbours when alive. The cell itself is part of the neighbourhood if
+/// its weight is not 0.
+///
+/// Offsets are given as `(dx, dy)`, with `dx` counting columns to the
+/// right and `dy` rows down. The range is always the smallest one that
+/// fits every cell of non-zero weight, so that two kernels are equal
+/// exactly when they weigh every cell the same.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Kernel {
+    range: u32,
+    // weights[(dy + range) * (2 * range + 1) + dx + range]
+    weights: Vec<u32>,
+}
+
+impl Kernel {
+    /// A kernel where every given offset has weight 1. Repeated offsets
+    /// add up. Fails if an offset is beyond `MAX_RANGE`, or an offset is
+    /// repeated more than `MAX_WEIGHT` times.
+    pub fn from_offsets(offsets: &[(i32, i32)]) -> Result<Kernel, Error> {
+        let weights: Vec<(i32, i32, u32)> = offsets.iter().map(|&(dx, dy)| (dx, dy, 1)).collect();
+        Kernel::from_weights(&weights)
+    }
+
+    /// A kernel from offsets `(dx, dy)` and their weights. The weights of
+    /// repeated offsets add up. Fails if an offset is beyond `MAX_RANGE`,
+    /// or a weight adds up to more than `MAX_WEIGHT`.
+    pub fn from_weights(weights: &[(i32, i32, u32)]) -> Result<Kernel, Error> {
+        let range = weights
+            .iter()
+            .filter(|w| w.2 > 0)
+            .map(|&(dx, dy, _)| dx.unsigned_abs().max(dy.unsigned_abs()))
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        if range > MAX_RANGE {
+            return Err(Error::InvalidArgument(format!("kernel offsets must be within {} cells", MAX_RANGE)));
+        }
+
+        let mut kernel = Kernel::empty(range);
+        for &(dx, dy, weight) in weights.iter().filter(|w| w.2 > 0) {
+            let i = kernel.index(dx, dy);
+            kernel.weights[i] = kernel.weights[i].saturating_add(weight);
+            if kernel.weights[i] > MAX_WEIGHT {
+                return Err(Error::InvalidArgument(format!(
+                    "the weight of offset ({}, {}) is more than {}",
+                    dx, dy, MAX_WEIGHT
+                )));
+            }
+        }
+
+        Ok(kernel)
+    }
+
+    /// Parse a kernel from an ASCII mask with an odd number of rows and of
+    /// columns, the cell itself being the one in the middle. `.` marks
+    /// cells outside the neighbourhood, `#` cells of weight 1 and a
+    /// hexadecimal digit the weight of a cell. For example, the cross:
+    ///
+    /// ```text
+    /// ..#..
+    /// ..#..
+    /// ##.##
+    /// ..#..
+    /// ..#..
+    /// ```
+    pub fn from_mask(mask: &str) -> Result<Kernel, Error> {
+        let rows: Vec<&str> = mask.lines().map(str::trim).filter(|row| !row.is_empty()).collect();
+        let width = rows.first().map_or(0, |row| row.chars().count());
+        if rows.len().is_multiple_of(2) || width.is_multiple_of(2) || rows.iter().any(|row| row.chars().count() != width) {
+            return Err(Error::ParseError(
+                "a kernel mask must have an odd number of rows and of columns of equal length".to_string(),
+            ));
+        }
+
+        let (half_height, half_width) = ((rows.len() / 2) as i32, (width / 2) as i32);
+        let mut weights = Vec::new();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                let weight = match c {
+                    '.' => 0,
+                    '#' => 1,
+                    _ => c
+                        .to_digit(16)
+                        .ok_or_else(|| Error::ParseError(format!("invalid character '{}' in kernel mask", c)))?,
+                };
+                weights.push((x as i32 - half_width, y as i32 - half_height, weight));
+            }
+        }
+
+        if half_width.max(half_height) as u32 > MAX_RANGE {
+            return Err(Error::ParseError(format!("a kernel mask must be within {} cells of its middle", MAX_RANGE)));
+        }
+
+        Kernel::from_weights(&weights)
+    }
+
+    /// The cells within the range in both directions, a square.
+    pub fn moore(range: u32) -> Kernel {
+        Kernel::from_fn(range, |_, _| true)
+    }
+
+    /// The cells within the range in Manhattan distance, a diamond.
+    pub fn von_neumann(range: u32) -> Kernel {
+        Kernel::from_fn(range, |dx, dy| dx.abs() + dy.abs() <= range as i32)
+    }
+
+    /// The cells within the range in the same row or column, a cross.
+    pub fn cross(range: u32) -> Kernel {
+        Kernel::from_fn(range, |dx, dy| dx == 0 || dy == 0)
+    }
+
+    /// The cells within the range on a hexagonal grid stored in axial
+    /// coordinates, as for `Neighborhood::Hexagonal`: the square without
+    /// the corners towards the north-east and south-west.
+    pub fn hexagonal(range: u32) -> Kernel {
+        Kernel::from_fn(range, |dx, dy| (dx - dy).abs() <= range as i32)
+    }
+
+    /// The range of the kernel, at least 1.
+    pub fn range(&self) -> u32 {
+        self.range
+    }
+
+    /// The weight of the cell at an offset, 0 if it is not part of the
+    /// neighbourhood.
+    pub fn weight(&self, dx: i32, dy: i32) -> u32 {
+        let r = self.range as i32;
+        if dx.abs() > r || dy.abs() > r {
+            return 0;
+        }
+
+        self.weights[self.index(dx, dy)]
+    }
+
+    /// The offsets `(dx, dy)` and weights of the cells of the kernel, row
+    /// by row.
+    pub fn offsets(&self) -> impl Iterator<Item = (i32, i32, u32)> + '_ {
+        let r = self.range as i32;
+        (-r..=r)
+            .flat_map(move |dy| (-r..=r).map(move |dx| (dx, dy)))
+            .map(move |(dx, dy)| (dx, dy, self.weight(dx, dy)))
+            .filter(|w| w.2 > 0)
+    }
+
+    /// The largest possible count, with every cell of the kernel alive.
+    pub fn total_weight(&self) -> u32 {
+        self.weights.iter().sum()
+    }
+
+    /// Returns whether every cell of the kernel has weight 0 or 1.
+    pub fn is_unweighted(&self) -> bool {
+        self.weights.iter().all(|&w| w <= 1)
+    }
+
+    /// The kernel without the cell itself.
+    pub fn without_center(&self) -> Kernel {
+        let mut kernel = self.clone();
+        let i = kernel.index(0, 0);
+        kernel.weights[i] = 0;
+        kernel
+    }
+
+    /// Parse LifeViewer's `N@` notation: the cells of a square of the
+    /// given range other than the middle one, row by row, as a bit mask in
+    /// hexadecimal with the first cell in the most significant bit and
+    /// padded with 0s to a whole digit.
+    pub fn from_hex_mask(range: u32, hex: &str) -> Result<Kernel, Error> {
+        let side = (2 * range + 1) as usize;
+        let bits = side * side - 1;
+        let digits = parse_hex_digits(hex, bits.div_ceil(4)).map_err(Error::ParseError)?;
+
+        let mut weights = Vec::new();
+        let cells = (0..side * side).filter(|&i| i != side * side / 2);
+        for (bit, i) in cells.enumerate() {
+            if digits[bit / 4] & (8 >> (bit % 4)) != 0 {
+                weights.push(((i % side) as i32 - range as i32, (i / side) as i32 - range as i32, 1));
+            }
+        }
+
+        Kernel::from_weights(&weights)
+    }
+
+    /// Parse LifeViewer's `NW` notation: the weight of every cell of a
+    /// square of the given range, row by row, as hexadecimal digits.
+    pub fn from_hex_weights(range: u32, hex: &str) -> Result<Kernel, Error> {
+        let side = (2 * range + 1) as usize;
+        let digits = parse_hex_digits(hex, side * side).map_err(Error::ParseError)?;
+
+        let weights: Vec<(i32, i32, u32)> = digits
+            .iter()
+            .enumerate()
+            .map(|(i, &w)| ((i % side) as i32 - range as i32, (i / side) as i32 - range as i32, w))
+            .collect();
+
+        Kernel::from_weights(&weights)
+    }
+
+    /// Write the kernel in `N@` notation, see `from_hex_mask`. The middle
+    /// cell is left out, and the weights must be 0 or 1.
+    pub fn to_hex_mask(&self) -> String {
+        let side = (2 * self.range + 1) as usize;
+        let mut bits: Vec<bool> = (0..side * side)
+            .filter(|&i| i != side * side / 2)
+            .map(|i| self.weights[i] > 0)
+            .collect();
+        bits.resize(bits.len().div_ceil(4) * 4, false);
+
+        bits.chunks(4)
+            .map(|nibble| nibble.iter().fold(0, |digit, &bit| digit << 1 | bit as u32))
+            .map(|digit| std::char::from_digit(digit, 16).unwrap().to_ascii_uppercase())
+            .collect()
+    }
+
+    /// Write the kernel in `NW` notation, see `from_hex_weights`.
+    pub fn to_hex_weights(&self) -> String {
+        self.weights
+            .iter()
+            .map(|&w| std::char::from_digit(w, 16).unwrap().to_ascii_uppercase())
+            .collect()
+    }
+
+    fn empty(range: u32) -> Kernel {
+        let side = (2 * range + 1) as usize;
+        Kernel { range, weights: vec![0; side * side] }
+    }
+
+    fn from_fn(range: u32, inside: impl Fn(i32, i32) -> bool) -> Kernel {
+        assert!(range <= MAX_RANGE, "kernel offsets must be within {} cells", MAX_RANGE);
+
+        let mut kernel = Kernel::empty(range.max(1));
+        let r = range as i32;
+        for (dx, dy) in (-r..=r).flat_map(|dy| (-r..=r).map(move |dx| (dx, dy))) {
+            if (dx, dy) != (0, 0) && inside(dx, dy) {
+                let i = kernel.index(dx, dy);
+                kernel.weights[i] = 1;
+            }
+        }
+        kernel
+    }
+
+    fn index(&self, dx: i32, dy: i32) -> usize {
+        let (r, side) = (self.range as i32, 2 * self.range as i32 + 1);
+        ((dy + r) * side + dx + r) as usize
+    }
+}
+
+/// Parses exactly `len` hexadecimal digits.
+fn parse_hex_digits(hex: &str, len: usize) -> Result<Vec<u32>, String> {
+    let digits: Vec<u32> = hex
+        .chars()
+        .map(|c| c.to_digit(16).ok_or_else(|| format!("invalid hexadecimal digit '{}' in neighbourhood", c)))
+        .collect::<Result<_, _>>()?;
+
+    if digits.len() != len {
+        return Err(format!("neighbourhood '{}' has {} digits instead of {}", hex, digits.len(), len));
+    }
+
+    Ok(digits)
+}
@@ -0,0 +1,51 @@
+//! Golly's `MAP` notation for arbitrary rules on the 3×3 neighbourhood.
+//!
+//! The 512 entries of the transition table, one per configuration of the
+//! neighbourhood, are packed into 64 bytes with the first entry in the most
+//! significant bit, and written in base64 without padding after `MAP`.
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// The number of base64 characters of a table, without padding.
+pub const LEN: usize = 86;
+
+/// Encodes a transition table as base64, without the `MAP` prefix.
+pub fn encode(map: &[bool; 512]) -> String {
+    let mut s = String::with_capacity(LEN);
+
+    // Every character holds 6 entries, and the last one is padded with 0s.
+    for chunk in map.chunks(6) {
+        let mut sextet = 0;
+        for i in 0..6 {
+            sextet = sextet << 1 | chunk.get(i).map_or(0, |&next| next as usize);
+        }
+        s.push(ALPHABET[sextet] as char);
+    }
+
+    s
+}
+
+/// Decodes a transition table from base64, without the `MAP` prefix. The
+/// `==` padding is optional.
+pub fn decode(s: &str) -> Result<[bool; 512], String> {
+    let s = s.strip_suffix("==").unwrap_or(s);
+    if s.len() != LEN {
+        return Err(format!("MAP table has {} characters instead of {}", s.len(), LEN));
+    }
+
+    let mut map = [false; 512];
+    for (i, c) in s.bytes().enumerate() {
+        let sextet = match ALPHABET.iter().position(|&a| a == c) {
+            Some(sextet) => sextet,
+            None => return Err(format!("invalid base64 character '{}' in MAP table", c as char)),
+        };
+
+        for bit in 0..6 {
+            if let Some(next) = map.get_mut(i * 6 + bit) {
+                *next = sextet & (0b100000 >> bit) != 0;
+            }
+        }
+    }
+
+    Ok(map)
+}
@@ -0,0 +1,150 @@
+//! 128 cells at a time for the bitwise generation step, with the `simd`
+//! feature.
+//!
+//! Built for WebAssembly with the `simd128` target feature enabled, as with
+//! `RUSTFLAGS="-C target-feature=+simd128"`, a word is a `v128` and every
+//! operation on it a single SIMD instruction. On other targets it is a pair
+//! of 64-bit words, which computes the same generations and can be checked
+//! natively.
+
+use std::ops::{BitAnd, BitOr, BitXor, Not};
+
+use crate::bitwise::{extract, Word};
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+mod lanes {
+    use core::arch::wasm32::{u64x2, u64x2_extract_lane, v128, v128_and, v128_not, v128_or, v128_xor};
+
+    /// 128 cells, the first ones in the lowest bits of the first lane.
+    #[derive(Clone, Copy)]
+    pub struct Lanes(v128);
+
+    impl Lanes {
+        #[inline]
+        pub fn new(low: u64, high: u64) -> Lanes {
+            Lanes(u64x2(low, high))
+        }
+
+        #[inline]
+        pub fn split(self) -> (u64, u64) {
+            (u64x2_extract_lane::<0>(self.0), u64x2_extract_lane::<1>(self.0))
+        }
+
+        #[inline]
+        pub fn and(self, other: Lanes) -> Lanes {
+            Lanes(v128_and(self.0, other.0))
+        }
+
+        #[inline]
+        pub fn or(self, other: Lanes) -> Lanes {
+            Lanes(v128_or(self.0, other.0))
+        }
+
+        #[inline]
+        pub fn xor(self, other: Lanes) -> Lanes {
+            Lanes(v128_xor(self.0, other.0))
+        }
+
+        #[inline]
+        pub fn not(self) -> Lanes {
+            Lanes(v128_not(self.0))
+        }
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+mod lanes {
+    /// 128 cells, the first ones in the lowest bits of the first word.
+    #[derive(Clone, Copy)]
+    pub struct Lanes([u64; 2]);
+
+    impl Lanes {
+        #[inline]
+        pub fn new(low: u64, high: u64) -> Lanes {
+            Lanes([low, high])
+        }
+
+        #[inline]
+        pub fn split(self) -> (u64, u64) {
+            (self.0[0], self.0[1])
+        }
+
+        #[inline]
+        pub fn and(self, other: Lanes) -> Lanes {
+            Lanes([self.0[0] & other.0[0], self.0[1] & other.0[1]])
+        }
+
+        #[inline]
+        pub fn or(self, other: Lanes) -> Lanes {
+            Lanes([self.0[0] | other.0[0], self.0[1] | other.0[1]])
+        }
+
+        #[inline]
+        pub fn xor(self, other: Lanes) -> Lanes {
+            Lanes([self.0[0] ^ other.0[0], self.0[1] ^ other.0[1]])
+        }
+
+        #[inline]
+        pub fn not(self) -> Lanes {
+            Lanes([!self.0[0], !self.0[1]])
+        }
+    }
+}
+
+pub use self::lanes::Lanes;
+
+impl BitAnd for Lanes {
+    type Output = Lanes;
+
+    #[inline]
+    fn bitand(self, other: Lanes) -> Lanes {
+        self.and(other)
+    }
+}
+
+impl BitOr for Lanes {
+    type Output = Lanes;
+
+    #[inline]
+    fn bitor(self, other: Lanes) -> Lanes {
+        self.or(other)
+    }
+}
+
+impl BitXor for Lanes {
+    type Output = Lanes;
+
+    #[inline]
+    fn bitxor(self, other: Lanes) -> Lanes {
+        self.xor(other)
+    }
+}
+
+impl Not for Lanes {
+    type Output = Lanes;
+
+    #[inline]
+    fn not(self) -> Lanes {
+        Lanes::not(self)
+    }
+}
+
+impl Word for Lanes {
+    const BITS: usize = 128;
+
+    fn zero() -> Lanes {
+        Lanes::new(0, 0)
+    }
+
+    #[inline]
+    fn load(padded: &[u64], bit: usize) -> Lanes {
+        Lanes::new(extract(padded, bit), extract(padded, bit + 64))
+    }
+
+    #[inline]
+    fn store(self, words: &mut [u64]) {
+        let (low, high) = self.split();
+        words[0] = low;
+        words[1] = high;
+    }
+}
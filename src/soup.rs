@@ -0,0 +1,179 @@
+//! Random soups: cells alive at random with some density, in a rectangle of
+//! a universe, optionally symmetric as the soups of apgsearch are.
+//!
+//! A soup is made from a seed with `Random`, so it is the same on every
+//! platform and can be replayed from its seed.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::Error;
+use crate::platform;
+use crate::random::Random;
+
+/// The symmetries of a soup. The symmetric images of a cell are taken
+/// within the rectangle of the soup, and have the same state as the cell.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Symmetry {
+    /// No symmetry: every cell is random.
+    #[default]
+    C1,
+    /// Turning the soup by a half turn.
+    C2,
+    /// Turning the soup by a quarter turn, for square soups.
+    C4,
+    /// Mirroring the soup left to right.
+    D2,
+    /// Mirroring the soup left to right and top to bottom.
+    D4,
+    /// Turning the soup by a quarter turn and mirroring it along any of its
+    /// axes or diagonals, for square soups.
+    D8,
+}
+
+/// A map of the cells of a `height` × `width` rectangle onto themselves,
+/// from a row and column to a row and column.
+type Transform = fn(u32, u32, u32, u32) -> (u32, u32);
+
+const IDENTITY: Transform = |row, column, _, _| (row, column);
+const HALF_TURN: Transform = |row, column, height, width| (height - 1 - row, width - 1 - column);
+const QUARTER_TURN: Transform = |row, column, height, _| (column, height - 1 - row);
+const THREE_QUARTER_TURN: Transform = |row, column, _, width| (width - 1 - column, row);
+const MIRROR_COLUMNS: Transform = |row, column, _, width| (row, width - 1 - column);
+const MIRROR_ROWS: Transform = |row, column, height, _| (height - 1 - row, column);
+const TRANSPOSE: Transform = |row, column, _, _| (column, row);
+const ANTI_TRANSPOSE: Transform = |row, column, height, width| (width - 1 - column, height - 1 - row);
+
+impl Symmetry {
+    /// Returns whether only square soups can have the symmetry.
+    pub fn needs_square(self) -> bool {
+        self == Symmetry::C4 || self == Symmetry::D8
+    }
+
+    /// Get the maps of a cell onto its symmetric images.
+    fn transforms(self) -> &'static [Transform] {
+        match self {
+            Symmetry::C1 => &[IDENTITY],
+            Symmetry::C2 => &[IDENTITY, HALF_TURN],
+            Symmetry::C4 => &[IDENTITY, QUARTER_TURN, HALF_TURN, THREE_QUARTER_TURN],
+            Symmetry::D2 => &[IDENTITY, MIRROR_COLUMNS],
+            Symmetry::D4 => &[IDENTITY, MIRROR_COLUMNS, MIRROR_ROWS, HALF_TURN],
+            Symmetry::D8 => &[
+                IDENTITY,
+                QUARTER_TURN,
+                HALF_TURN,
+                THREE_QUARTER_TURN,
+                MIRROR_COLUMNS,
+                MIRROR_ROWS,
+                TRANSPOSE,
+                ANTI_TRANSPOSE,
+            ],
+        }
+    }
+}
+
+impl FromStr for Symmetry {
+    type Err = Error;
+
+    /// Parse the name of a symmetry: `C1`, `C2`, `C4`, `D2`, `D4` or `D8`.
+    fn from_str(s: &str) -> Result<Symmetry, Error> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "C1" => Ok(Symmetry::C1),
+            "C2" => Ok(Symmetry::C2),
+            "C4" => Ok(Symmetry::C4),
+            "D2" => Ok(Symmetry::D2),
+            "D4" => Ok(Symmetry::D4),
+            "D8" => Ok(Symmetry::D8),
+            _ => Err(Error::ParseError(format!("unknown symmetry '{}', expected C1, C2, C4, D2, D4 or D8", s))),
+        }
+    }
+}
+
+impl fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Symmetry::C1 => "C1",
+            Symmetry::C2 => "C2",
+            Symmetry::C4 => "C4",
+            Symmetry::D2 => "D2",
+            Symmetry::D4 => "D4",
+            Symmetry::D8 => "D8",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How to fill a universe with random cells.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Soup {
+    /// The seed the cells are drawn from.
+    pub seed: u64,
+    /// The chance of every cell to be alive, from 0 to 1.
+    pub density: f64,
+    /// The rectangle of the soup, as its top row, left column, height and
+    /// width, or `None` for the entire universe. The cells outside of it
+    /// are dead.
+    pub region: Option<(u32, u32, u32, u32)>,
+    pub symmetry: Symmetry,
+}
+
+impl Soup {
+    /// Create an asymmetric soup of the entire universe from a seed, with
+    /// half of the cells alive.
+    pub fn new(seed: u64) -> Soup {
+        Soup { seed, density: 0.5, region: None, symmetry: Symmetry::C1 }
+    }
+
+    /// Create a soup like `new` from a seed drawn from the platform, see
+    /// `platform::random`.
+    pub fn random() -> Soup {
+        let bits = || (platform::random() * (1u64 << 32) as f64) as u64;
+        Soup::new(bits() << 32 | bits())
+    }
+
+    /// Get the row and column of every live cell of the soup in a `width` ×
+    /// `height` universe, row by row. Fails if the density is not between 0
+    /// and 1, if the rectangle of the soup does not fit in the universe, or
+    /// if it is not square and the symmetry needs it to be.
+    pub fn cells(&self, width: u32, height: u32) -> Result<Vec<(u32, u32)>, Error> {
+        if !(0.0..=1.0).contains(&self.density) {
+            return Err(Error::InvalidArgument(format!("soup density {} is not between 0 and 1", self.density)));
+        }
+        let (top, left, rows, columns) = self.region.unwrap_or((0, 0, height, width));
+        if top as u64 + rows as u64 > height as u64 || left as u64 + columns as u64 > width as u64 {
+            return Err(Error::InvalidArgument(format!(
+                "soup of {}×{} cells from row {} and column {} does not fit in a {}×{} universe",
+                columns, rows, top, left, width, height
+            )));
+        }
+        if self.symmetry.needs_square() && rows != columns {
+            return Err(Error::InvalidArgument(format!(
+                "{} soups must be square, not {}×{}",
+                self.symmetry, columns, rows
+            )));
+        }
+
+        // A cell is drawn from the generator unless one of its images comes
+        // before it, row by row, which it then copies.
+        let transforms = self.symmetry.transforms();
+        let mut random = Random::new(self.seed);
+        let mut alive = vec![false; (rows * columns) as usize];
+        for i in 0..alive.len() {
+            let (row, column) = (i as u32 / columns, i as u32 % columns);
+            let first = transforms
+                .iter()
+                .map(|transform| {
+                    let (r, c) = transform(row, column, rows, columns);
+                    (r * columns + c) as usize
+                })
+                .min()
+                .unwrap_or(i);
+            alive[i] = if first < i { alive[first] } else { random.next_f64() < self.density };
+        }
+
+        Ok((0..alive.len())
+            .filter(|&i| alive[i])
+            .map(|i| (top + i as u32 / columns, left + i as u32 % columns))
+            .collect())
+    }
+}
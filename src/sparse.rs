@@ -0,0 +1,327 @@
+//! An unbounded universe, storing only the regions around live cells.
+//!
+//! The plane is cut into square chunks of `CHUNK_SIZE` cells, kept in a
+//! hash map by their position, with one 64-bit word per row of a chunk.
+//! A chunk is allocated when a cell in it becomes alive, and freed as soon
+//! as all its cells are dead, so memory follows the pattern as it moves,
+//! grows or dies out.
+
+use std::collections::{HashMap, HashSet};
+
+use wasm_bindgen::prelude::*;
+
+use crate::error::Error;
+use crate::patterns;
+use crate::rule::Rule;
+use crate::utils;
+use crate::Timer;
+
+/// The number of rows and columns of a chunk.
+pub const CHUNK_SIZE: i64 = 64;
+
+/// The 3 bits of a row of a 3×3 neighbourhood in reverse order, from the
+/// west-most cell in the lowest bit to the west-most in the highest one,
+/// as `config_bit` wants them.
+const REVERSED: [usize; 8] = [0b000, 0b100, 0b010, 0b110, 0b001, 0b101, 0b011, 0b111];
+
+/// The cells of a chunk, one word per row, with the west-most column in the
+/// lowest bit.
+#[derive(Clone)]
+struct Chunk {
+    rows: [u64; CHUNK_SIZE as usize],
+}
+
+impl Chunk {
+    fn empty() -> Chunk {
+        Chunk { rows: [0; CHUNK_SIZE as usize] }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.rows.iter().all(|&row| row == 0)
+    }
+
+    /// Returns whether a live cell lies along the edge or corner shared
+    /// with the neighbouring chunk `dy` chunks down and `dx` to the right,
+    /// so that cells may be born in it.
+    fn touches(&self, dy: i64, dx: i64) -> bool {
+        let last = CHUNK_SIZE as usize - 1;
+        let rows = match dy {
+            -1 => &self.rows[..1],
+            1 => &self.rows[last..],
+            _ => &self.rows[..],
+        };
+        let mask = match dx {
+            -1 => 1,
+            1 => 1 << last,
+            _ => !0,
+        };
+
+        rows.iter().any(|&row| row & mask != 0)
+    }
+}
+
+/// Returns an error if a rule cannot run in a universe without edges: only
+/// rules that look at the 8 immediate neighbours, or the 6 of a hexagonal
+/// grid, with two states and without B0 can.
+pub(crate) fn check_rule(rule: &Rule) -> Result<(), Error> {
+    if !rule.is_life_like() || rule.is_generations() {
+        return Err(Error::UnsupportedRule(format!("rule '{}' is not supported by an unbounded universe", rule)));
+    }
+    if rule.has_b0() {
+        return Err(Error::UnsupportedRule(format!(
+            "rule '{}' has B0, which an unbounded universe cannot emulate",
+            rule
+        )));
+    }
+
+    Ok(())
+}
+
+/// A universe without edges, where a pattern can travel as far as signed
+/// 64-bit coordinates reach. Only rules that look at the 8 immediate
+/// neighbours, or the 6 of a hexagonal grid, with two states and without
+/// B0 are supported: with B0, the infinite background would be born.
+#[wasm_bindgen]
+pub struct SparseUniverse {
+    // The chunks holding at least one live cell, by row and column of
+    // chunks.
+    chunks: HashMap<(i64, i64), Chunk>,
+    rule: Rule,
+}
+
+impl SparseUniverse {
+    /// Split a cell position into the position of its chunk and its row
+    /// and column within the chunk.
+    fn locate(row: i64, column: i64) -> ((i64, i64), usize, u32) {
+        let key = (row.div_euclid(CHUNK_SIZE), column.div_euclid(CHUNK_SIZE));
+        (key, row.rem_euclid(CHUNK_SIZE) as usize, column.rem_euclid(CHUNK_SIZE) as u32)
+    }
+
+    pub(crate) fn set_cell(&mut self, row: i64, column: i64, alive: bool) {
+        let (key, r, c) = SparseUniverse::locate(row, column);
+        if alive {
+            self.chunks.entry(key).or_insert_with(Chunk::empty).rows[r] |= 1 << c;
+        } else if let Some(chunk) = self.chunks.get_mut(&key) {
+            chunk.rows[r] &= !(1 << c);
+            if chunk.is_empty() {
+                self.chunks.remove(&key);
+            }
+        }
+    }
+
+    /// Get a row of a chunk, where rows -1 and `CHUNK_SIZE` are the
+    /// nearest ones of the chunks above and below.
+    fn chunk_row(&self, (cy, cx): (i64, i64), row: i64) -> u64 {
+        let key = (cy + row.div_euclid(CHUNK_SIZE), cx);
+        self.chunks.get(&key).map_or(0, |chunk| chunk.rows[row.rem_euclid(CHUNK_SIZE) as usize])
+    }
+
+    /// Compute the next generation of a chunk.
+    fn next_chunk(&self, (cy, cx): (i64, i64)) -> Chunk {
+        // The rows of the chunk and the ones around it, each with the
+        // nearest column of the chunks to the west and east: bit `c + 1` of
+        // `window[r + 1]` is the cell in row `r` and column `c`.
+        let mut window = [0u128; CHUNK_SIZE as usize + 2];
+        for (i, bits) in window.iter_mut().enumerate() {
+            let row = i as i64 - 1;
+            let west = self.chunk_row((cy, cx - 1), row) >> (CHUNK_SIZE - 1);
+            let middle = self.chunk_row((cy, cx), row);
+            let east = self.chunk_row((cy, cx + 1), row) & 1;
+            *bits = west as u128 | (middle as u128) << 1 | (east as u128) << (CHUNK_SIZE + 1);
+        }
+
+        let mut next = Chunk::empty();
+        for (r, rows) in window.windows(3).enumerate() {
+            let (north, middle, south) = (rows[0], rows[1], rows[2]);
+            if north | middle | south == 0 {
+                continue;
+            }
+
+            for c in 0..CHUNK_SIZE as u32 {
+                let bits = |row: u128| REVERSED[(row >> c) as usize & 0b111];
+                let config = bits(north) << 6 | bits(middle) << 3 | bits(south);
+                if self.rule.next_state_from_config(config) {
+                    next.rows[r] |= 1 << c;
+                }
+            }
+        }
+
+        next
+    }
+
+    fn stamp(&mut self, row: i64, column: i64, pattern: &[bool]) {
+        let size = (pattern.len() as f64).sqrt() as usize;
+        let half = (size / 2) as i64;
+
+        for (i, &alive) in pattern.iter().enumerate() {
+            let r = row - half + (i / size) as i64;
+            let c = column - half + (i % size) as i64;
+            self.set_cell(r, c, alive);
+        }
+    }
+}
+
+/// Public methods, exported to JavaScript.
+#[wasm_bindgen]
+impl SparseUniverse {
+    pub fn tick(&mut self) {
+        let _timer = Timer::new("SparseUniverse::tick");
+
+        // Every chunk with live cells may change, and so may its neighbours
+        // where live cells lie along their shared edges.
+        let mut keys = HashSet::new();
+        for (&(cy, cx), chunk) in &self.chunks {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    if chunk.touches(dy, dx) {
+                        keys.insert((cy + dy, cx + dx));
+                    }
+                }
+            }
+        }
+
+        let mut next = HashMap::with_capacity(keys.len());
+        for key in keys {
+            let chunk = self.next_chunk(key);
+            if !chunk.is_empty() {
+                next.insert(key, chunk);
+            }
+        }
+
+        self.chunks = next;
+    }
+
+    /// Create an empty universe following Conway's Game of Life.
+    pub fn new() -> SparseUniverse {
+        utils::set_panic_hook();
+
+        SparseUniverse {
+            chunks: HashMap::new(),
+            rule: Rule::default(),
+        }
+    }
+
+    pub fn reset_all_dead(&mut self) {
+        self.chunks.clear();
+    }
+
+    /// Get the rule of the universe in B/S notation.
+    pub fn rule(&self) -> String {
+        self.rule.to_string()
+    }
+
+    /// Set the rule of the universe from a rulestring, as for
+    /// `Universe::set_rule`. Rules looking beyond the 8 immediate
+    /// neighbours, Generations rules and rules with B0 are rejected.
+    pub fn set_rule(&mut self, rule: &str) -> Result<(), Error> {
+        let rule: Rule = rule.parse()?;
+        check_rule(&rule)?;
+        self.rule = rule;
+        Ok(())
+    }
+
+    /// Returns whether the cell in a row and column is alive.
+    pub fn is_alive(&self, row: i64, column: i64) -> bool {
+        let (key, r, c) = SparseUniverse::locate(row, column);
+        self.chunks.get(&key).is_some_and(|chunk| chunk.rows[r] & 1 << c != 0)
+    }
+
+    /// Toggle a cell between dead and alive.
+    pub fn toggle_cell(&mut self, row: i64, column: i64) {
+        let alive = self.is_alive(row, column);
+        self.set_cell(row, column, !alive);
+    }
+
+    /// Insert a glider around a cell.
+    pub fn insert_glider(&mut self, row: i64, column: i64) {
+        self.stamp(row, column, &patterns::GLIDER);
+    }
+
+    /// Insert a pulsar around a cell.
+    pub fn insert_pulsar(&mut self, row: i64, column: i64) {
+        self.stamp(row, column, &patterns::PULSAR);
+    }
+
+    /// Get the number of live cells.
+    pub fn population(&self) -> u64 {
+        self.chunks
+            .values()
+            .flat_map(|chunk| chunk.rows.iter())
+            .map(|row| row.count_ones() as u64)
+            .sum()
+    }
+
+    /// Get the number of chunks currently allocated, see `CHUNK_SIZE`.
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+}
+
+impl SparseUniverse {
+    /// Get the rule of the universe.
+    pub fn get_rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    /// Replace the rule of the universe, returning the previous one, or an
+    /// error if the universe does not support the rule, see `set_rule`.
+    pub fn replace_rule(&mut self, rule: Rule) -> Result<Rule, Error> {
+        check_rule(&rule)?;
+        Ok(std::mem::replace(&mut self.rule, rule))
+    }
+
+    /// Set cells to be alive in a universe by passing the row and column
+    /// of each cell as an array.
+    pub fn set_cells(&mut self, cells: &[(i64, i64)]) {
+        for &(row, column) in cells {
+            self.set_cell(row, column, true);
+        }
+    }
+
+    /// Get the row and column of every live cell, row by row.
+    pub fn live_cells(&self) -> Vec<(i64, i64)> {
+        let mut cells = Vec::new();
+        for (&(cy, cx), chunk) in &self.chunks {
+            for (r, &row) in chunk.rows.iter().enumerate() {
+                let mut bits = row;
+                while bits != 0 {
+                    let c = bits.trailing_zeros() as i64;
+                    cells.push((cy * CHUNK_SIZE + r as i64, cx * CHUNK_SIZE + c));
+                    bits &= bits - 1;
+                }
+            }
+        }
+
+        cells.sort_unstable();
+        cells
+    }
+
+    /// Get the smallest rectangle holding every live cell, as its top row,
+    /// left column, bottom row and right column, or `None` if every cell is
+    /// dead.
+    pub fn bounding_box(&self) -> Option<(i64, i64, i64, i64)> {
+        let mut bounds: Option<(i64, i64, i64, i64)> = None;
+        for (&(cy, cx), chunk) in &self.chunks {
+            let columns = chunk.rows.iter().fold(0, |columns, &row| columns | row);
+            let top = chunk.rows.iter().position(|&row| row != 0).unwrap_or(0) as i64;
+            let bottom = chunk.rows.iter().rposition(|&row| row != 0).unwrap_or(0) as i64;
+            let left = columns.trailing_zeros() as i64;
+            let right = CHUNK_SIZE - 1 - columns.leading_zeros() as i64;
+
+            let (top, left) = (cy * CHUNK_SIZE + top, cx * CHUNK_SIZE + left);
+            let (bottom, right) = (cy * CHUNK_SIZE + bottom, cx * CHUNK_SIZE + right);
+            bounds = Some(match bounds {
+                Some((t, l, b, r)) => (t.min(top), l.min(left), b.max(bottom), r.max(right)),
+                None => (top, left, bottom, right),
+            });
+        }
+
+        bounds
+    }
+}
+
+impl Default for SparseUniverse {
+    fn default() -> SparseUniverse {
+        SparseUniverse::new()
+    }
+}
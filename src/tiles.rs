@@ -0,0 +1,256 @@
+//! Tracking the tiles of a universe that changed in the last generation, so
+//! that a generation step only recomputes them and the tiles around them.
+//!
+//! A tile is recomputed if its own cells or the cells around it changed,
+//! with 2×2 cells at a time looked up as in `lookup`. The other tiles are
+//! stable: their next generation is their current one, which the back
+//! buffer already holds from the generation before. Under a B0 rule whose
+//! background flips every generation, no tile is stable.
+
+use fixedbitset::FixedBitSet;
+
+use crate::bitwise::{read_bits, write_bits};
+use crate::lookup::Table;
+use crate::topology::Topology;
+
+/// The number of rows and columns of a tile.
+pub const TILE_SIZE: u32 = 32;
+
+/// The tiles of a universe and what changed in them.
+pub struct Tiles {
+    width: u32,
+    height: u32,
+    columns: u32,
+    // sources[tile]: the other tiles holding the cells around a tile, which
+    // its next generation depends on.
+    sources: Vec<Vec<u32>>,
+    // The tiles whose cells differ from the back buffer, and where the
+    // tiles changing in the next generation are collected.
+    changed: FixedBitSet,
+    next_changed: FixedBitSet,
+    // The number of set bits of every tile, relative to the background.
+    populations: Vec<u32>,
+    population: u32,
+    active: u32,
+    changed_cells: u32,
+}
+
+impl Tiles {
+    /// Split a universe into tiles, all of them changed.
+    pub fn new(cells: &FixedBitSet, width: u32, height: u32, topology: Topology) -> Tiles {
+        let (columns, rows) = (width.div_ceil(TILE_SIZE), height.div_ceil(TILE_SIZE));
+        let tile_of = |row: u32, column: u32| row / TILE_SIZE * columns + column / TILE_SIZE;
+
+        let mut sources = Vec::with_capacity((columns * rows) as usize);
+        let mut populations = Vec::with_capacity((columns * rows) as usize);
+        for tile in 0..columns * rows {
+            let (top, left) = ((tile / columns * TILE_SIZE) as i64, (tile % columns * TILE_SIZE) as i64);
+            let bottom = (top + TILE_SIZE as i64).min(height as i64);
+            let right = (left + TILE_SIZE as i64).min(width as i64);
+
+            // The ring of cells around the tile.
+            let ring = (left - 1..=right)
+                .flat_map(|column| [(top - 1, column), (bottom, column)])
+                .chain((top..bottom).flat_map(|row| [(row, left - 1), (row, right)]));
+            let mut tiles: Vec<u32> = ring
+                .filter_map(|(row, column)| topology.wrap(row, column, width, height))
+                .map(|(row, column)| tile_of(row, column))
+                .filter(|&source| source != tile)
+                .collect();
+            tiles.sort_unstable();
+            tiles.dedup();
+            sources.push(tiles);
+
+            let population = (top..bottom)
+                .map(|row| {
+                    let start = (row as u32 * width) as usize + left as usize;
+                    read_bits(cells.as_slice(), start, (right - left) as usize).count_ones()
+                })
+                .sum();
+            populations.push(population);
+        }
+
+        let mut changed = FixedBitSet::with_capacity(sources.len());
+        changed.insert_range(..);
+        Tiles {
+            width,
+            height,
+            columns,
+            next_changed: FixedBitSet::with_capacity(sources.len()),
+            sources,
+            changed,
+            population: populations.iter().sum(),
+            populations,
+            active: 0,
+            changed_cells: 0,
+        }
+    }
+
+    /// Record that a set bit was flipped outside of a generation step.
+    pub fn flip(&mut self, index: usize, set: bool) {
+        let (row, column) = (index as u32 / self.width, index as u32 % self.width);
+        let tile = (row / TILE_SIZE * self.columns + column / TILE_SIZE) as usize;
+        self.changed.insert(tile);
+        if set {
+            self.populations[tile] += 1;
+            self.population += 1;
+        } else {
+            self.populations[tile] -= 1;
+            self.population -= 1;
+        }
+    }
+
+    /// Get the number of live cells.
+    pub fn population(&self, background: bool) -> u32 {
+        if background {
+            self.width * self.height - self.population
+        } else {
+            self.population
+        }
+    }
+
+    /// Get the number of tiles recomputed by the last generation step.
+    pub fn active(&self) -> u32 {
+        self.active
+    }
+
+    /// Get the number of cells whose state changed in the last generation
+    /// step.
+    pub fn changed_cells(&self) -> u32 {
+        self.changed_cells
+    }
+
+    /// Compute the next generation of the tiles that changed or that are
+    /// around changed cells into `next`, which must hold the generation
+    /// before `cells` wherever no tile changed since.
+    #[allow(clippy::too_many_arguments)]
+    pub fn tick(
+        &mut self,
+        table: &Table,
+        cells: &FixedBitSet,
+        topology: Topology,
+        background: bool,
+        next_background: bool,
+        next: &mut FixedBitSet,
+    ) {
+        let mut changed = std::mem::take(&mut self.next_changed);
+        changed.clear();
+        let (mut active, mut changed_cells) = (0, 0);
+
+        // A background that flips every generation alternates the tables
+        // from set bits to set bits, so a tile that did not change under
+        // one may change under the other: every tile is recomputed.
+        let flips = background != next_background;
+        for tile in 0..self.sources.len() {
+            if !flips && !self.changed[tile] && !self.sources[tile].iter().any(|&source| self.changed[source as usize]) {
+                continue;
+            }
+
+            let (top, left) = (tile as u32 / self.columns * TILE_SIZE, tile as u32 % self.columns * TILE_SIZE);
+            let (population, changes) = self.tick_tile(table, cells, topology, background, top, left, next);
+            self.population = self.population - self.populations[tile] + population;
+            self.populations[tile] = population;
+            changed.set(tile, changes != 0);
+            active += 1;
+            changed_cells += changes;
+        }
+
+        self.next_changed = std::mem::replace(&mut self.changed, changed);
+        self.active = active;
+        // When the background flips, every cell whose set bit did not change
+        // changes state.
+        self.changed_cells = if background != next_background {
+            self.width * self.height - changed_cells
+        } else {
+            changed_cells
+        };
+    }
+
+    /// Compute the next generation of the tile at a row and column into
+    /// `next`, returning its number of set bits and of changed cells.
+    #[allow(clippy::too_many_arguments)]
+    fn tick_tile(
+        &self,
+        table: &Table,
+        cells: &FixedBitSet,
+        topology: Topology,
+        background: bool,
+        top: u32,
+        left: u32,
+        next: &mut FixedBitSet,
+    ) -> (u32, u32) {
+        let (width, height) = (self.width, self.height);
+        let (rows, columns) = ((height - top).min(TILE_SIZE), (width - left).min(TILE_SIZE) as usize);
+        let tiles = table.tiles(background);
+        let mask = (1 << columns) - 1;
+
+        // Bit `c` of a segment is the cell in column `left + c - 1`.
+        let segment = |row: u32| read_segment(cells, width, height, topology, background, row as i64 - 1, left as i64 - 1);
+        let mut window = [segment(top), segment(top + 1), 0, 0];
+        let (mut population, mut changes) = (0, 0);
+
+        for pair in (0..rows).step_by(2) {
+            window[2] = segment(top + pair + 2);
+            window[3] = segment(top + pair + 3);
+
+            let mut next_rows = [0u64; 2];
+            for j in 0..columns.div_ceil(2) {
+                let block = window
+                    .iter()
+                    .enumerate()
+                    .fold(0, |block, (i, &row)| block | ((row >> (2 * j) & 0b1111) as usize) << (4 * i));
+                let tile = tiles[block] as u64;
+                next_rows[0] |= (tile & 0b11) << (2 * j);
+                next_rows[1] |= (tile >> 2 & 0b11) << (2 * j);
+            }
+
+            for (i, &row) in next_rows.iter().enumerate().take((rows - pair) as usize) {
+                let start = ((top + pair + i as u32) * width + left) as usize;
+                let row = row & mask;
+                population += row.count_ones();
+                changes += (row ^ read_bits(cells.as_slice(), start, columns)).count_ones();
+                write_bits(next.as_mut_slice(), start, row, columns);
+            }
+
+            window = [window[2], window[3], 0, 0];
+        }
+
+        (population, changes)
+    }
+}
+
+/// Read `TILE_SIZE + 2` cells of a row from a column, either of which may
+/// lie beyond the edges. Cells beyond the edges of a plane read as
+/// `outside`.
+fn read_segment(
+    cells: &FixedBitSet,
+    width: u32,
+    height: u32,
+    topology: Topology,
+    outside: bool,
+    row: i64,
+    first: i64,
+) -> u64 {
+    let end = first + TILE_SIZE as i64 + 2;
+    let (start, stop) = if (0..height as i64).contains(&row) {
+        (first.clamp(0, width as i64), end.clamp(0, width as i64))
+    } else {
+        (first, first)
+    };
+
+    let mut bits = 0;
+    if start < stop {
+        let index = row as usize * width as usize + start as usize;
+        bits = read_bits(cells.as_slice(), index, (stop - start) as usize) << (start - first);
+    }
+
+    // The cells across the edges.
+    for column in (first..start).chain(stop.max(start)..end) {
+        let alive = match topology.wrap(row, column, width, height) {
+            Some((r, c)) => cells[(r * width + c) as usize],
+            None => outside,
+        };
+        bits |= (alive as u64) << (column - first);
+    }
+    bits
+}
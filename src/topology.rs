@@ -0,0 +1,197 @@
+//! The shapes a bounded universe can be glued into at its edges, following
+//! Golly's bounded grids.
+
+use crate::error::Error;
+
+/// A pair of opposite edges of the universe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edges {
+    /// The top and bottom edges.
+    Horizontal,
+    /// The left and right edges.
+    Vertical,
+}
+
+/// How the edges of a universe are joined, which decides the neighbours of
+/// the cells along them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Topology {
+    /// Opposite edges are joined, so that a pattern leaving through one
+    /// edge comes back through the other.
+    #[default]
+    Torus,
+    /// Like `Torus`, but a pattern crossing the given edges moves along
+    /// them by `shift` cells: right for every crossing of the bottom edge,
+    /// down for every crossing of the right edge.
+    ShiftedTorus { edges: Edges, shift: i32 },
+    /// The edges are not joined, and the cells beyond them are dead.
+    Plane,
+    /// Opposite edges are joined, the given pair with a reversal, so that
+    /// a pattern crossing them comes back mirrored.
+    KleinBottle { twisted: Edges },
+    /// Both pairs of opposite edges are joined with a reversal.
+    CrossSurface,
+    /// The top edge is joined to the left edge and the bottom edge to the
+    /// right edge. Only square universes can be spheres.
+    Sphere,
+}
+
+impl Topology {
+    /// Find the cell at a row and column that may lie beyond the edges of
+    /// a `width` × `height` universe, or `None` if there is no cell there.
+    pub fn wrap(self, row: i64, column: i64, width: u32, height: u32) -> Option<(u32, u32)> {
+        let (w, h) = (width as i64, height as i64);
+        if (0..h).contains(&row) && (0..w).contains(&column) {
+            return Some((row as u32, column as u32));
+        }
+
+        // How many times the position crosses each pair of edges, and
+        // where it lands with plain wrapping.
+        let (down, right) = (row.div_euclid(h), column.div_euclid(w));
+        let (mut r, mut c) = (row.rem_euclid(h), column.rem_euclid(w));
+
+        match self {
+            Topology::Torus => {}
+            Topology::ShiftedTorus { edges: Edges::Horizontal, shift } => {
+                c = (c + down * shift as i64).rem_euclid(w);
+            }
+            Topology::ShiftedTorus { edges: Edges::Vertical, shift } => {
+                r = (r + right * shift as i64).rem_euclid(h);
+            }
+            Topology::Plane => return None,
+            Topology::KleinBottle { twisted: Edges::Horizontal } => {
+                if down % 2 != 0 {
+                    c = w - 1 - c;
+                }
+            }
+            Topology::KleinBottle { twisted: Edges::Vertical } => {
+                if right % 2 != 0 {
+                    r = h - 1 - r;
+                }
+            }
+            Topology::CrossSurface => {
+                if down % 2 != 0 {
+                    c = w - 1 - c;
+                }
+                if right % 2 != 0 {
+                    r = h - 1 - r;
+                }
+            }
+            Topology::Sphere => {
+                // Reflect across the diagonal through the joined corner,
+                // for positions beyond a single edge.
+                let (r, c) = match (down, right) {
+                    (-1, 0) => (column, -1 - row),
+                    (0, -1) => (-1 - column, row),
+                    (1, 0) => (column, w + h - 1 - row),
+                    (0, 1) => (w + h - 1 - column, row),
+                    _ => return None,
+                };
+                if !(0..h).contains(&r) || !(0..w).contains(&c) {
+                    return None;
+                }
+                return Some((r as u32, c as u32));
+            }
+        }
+
+        Some((r as u32, c as u32))
+    }
+
+    /// Parse a Golly grid specification such as `T64,64`, `T64,64+2`,
+    /// `P30,20`, `K64*,64`, `C64,64` or `S64`, into the topology and the
+    /// width and height of the universe. A `*` after the width twists the
+    /// top and bottom edges of a Klein bottle, and after the height the left
+    /// and right edges. A `+` shift after the width shifts the top and
+    /// bottom edges of a torus, and after the height the left and right
+    /// edges.
+    pub fn parse(spec: &str) -> Result<(Topology, u32, u32), Error> {
+        parse_spec(spec).map_err(Error::ParseError)
+    }
+
+    /// Write the Golly grid specification of a `width` × `height` universe
+    /// with this topology, see `parse`.
+    pub fn to_spec(self, width: u32, height: u32) -> String {
+        match self {
+            Topology::Torus => format!("T{},{}", width, height),
+            Topology::ShiftedTorus { edges: Edges::Horizontal, shift } => format!("T{}{:+},{}", width, shift, height),
+            Topology::ShiftedTorus { edges: Edges::Vertical, shift } => format!("T{},{}{:+}", width, height, shift),
+            Topology::Plane => format!("P{},{}", width, height),
+            Topology::KleinBottle { twisted: Edges::Horizontal } => format!("K{}*,{}", width, height),
+            Topology::KleinBottle { twisted: Edges::Vertical } => format!("K{},{}*", width, height),
+            Topology::CrossSurface => format!("C{},{}", width, height),
+            Topology::Sphere => format!("S{}", width),
+        }
+    }
+}
+
+/// Parse a Golly grid specification, see `Topology::parse`.
+fn parse_spec(spec: &str) -> Result<(Topology, u32, u32), String> {
+    let spec = spec.trim();
+    let mut chars = spec.chars();
+    let kind = chars.next().map(|c| c.to_ascii_uppercase());
+    let sizes: Vec<&str> = chars.as_str().split(',').map(str::trim).collect();
+
+    let (width, height) = match (kind, &sizes[..]) {
+        (Some('S'), [size]) => (Size::parse(size)?, Size::parse(size)?),
+        (Some('S'), _) => return Err(format!("sphere '{}' must have a single size", spec)),
+        (_, [width, height]) => (Size::parse(width)?, Size::parse(height)?),
+        _ => return Err(format!("grid '{}' must have a width and a height", spec)),
+    };
+
+    let twisted = match (width.twisted, height.twisted) {
+        (false, false) => None,
+        (true, false) => Some(Edges::Horizontal),
+        (false, true) => Some(Edges::Vertical),
+        (true, true) => return Err(format!("grid '{}' twists both pairs of edges", spec)),
+    };
+    let shifted = match (width.shift, height.shift) {
+        (0, 0) => None,
+        (shift, 0) => Some((Edges::Horizontal, shift)),
+        (0, shift) => Some((Edges::Vertical, shift)),
+        _ => return Err(format!("grid '{}' shifts both pairs of edges", spec)),
+    };
+
+    let topology = match (kind, twisted, shifted) {
+        (Some('T'), None, None) => Topology::Torus,
+        (Some('T'), None, Some((edges, shift))) => Topology::ShiftedTorus { edges, shift },
+        (Some('P'), None, None) => Topology::Plane,
+        (Some('K'), Some(twisted), None) => Topology::KleinBottle { twisted },
+        (Some('K'), None, _) => return Err(format!("Klein bottle '{}' needs a '*' on one size", spec)),
+        (Some('C'), None, None) => Topology::CrossSurface,
+        (Some('S'), None, None) => Topology::Sphere,
+        (Some('T'), ..) | (Some('P'), ..) | (Some('K'), ..) | (Some('C'), ..) | (Some('S'), ..) => {
+            return Err(format!("grid '{}' has a twist or shift it does not support", spec));
+        }
+        _ => return Err(format!("invalid grid type in '{}', expected T, P, K, C or S", spec)),
+    };
+
+    Ok((topology, width.size, height.size))
+}
+
+/// One size of a grid specification, such as `64`, `64*` or `64+2`.
+struct Size {
+    size: u32,
+    twisted: bool,
+    shift: i32,
+}
+
+impl Size {
+    fn parse(s: &str) -> Result<Size, String> {
+        let (s, shift) = match s.find(['+', '-']) {
+            Some(i) => {
+                let shift = s[i..].parse().map_err(|_| format!("invalid shift '{}'", &s[i..]))?;
+                (&s[..i], shift)
+            }
+            None => (s, 0),
+        };
+        let (s, twisted) = match s.strip_suffix('*') {
+            Some(s) => (s, true),
+            None => (s, false),
+        };
+
+        match s.parse() {
+            Ok(size) if size > 0 => Ok(Size { size, twisted, shift }),
+            _ => Err(format!("invalid grid size '{}'", s)),
+        }
+    }
+}
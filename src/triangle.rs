@@ -0,0 +1,72 @@
+//! Geometry of triangular universes, for counting neighbours, drawing and
+//! hit-testing cells.
+//!
+//! A triangular universe is stored on the usual square grid. The cell in a
+//! row and column is a triangle pointing up when the sum of its row and
+//! column is even, and down otherwise, so every row alternates between the
+//! two and neighbouring columns overlap by half a triangle. The universe
+//! only tiles seamlessly around the torus if its width and height are
+//! even.
+
+/// The row and column offsets of the 3 triangles sharing an edge with a
+/// triangle pointing down (first) or up (second).
+pub const EDGE_NEIGHBORS: [[(i32, i32); 3]; 2] = [
+    [(-1, 0), (0, -1), (0, 1)],
+    [(0, -1), (0, 1), (1, 0)],
+];
+
+/// The row and column offsets of the 12 triangles sharing a corner with a
+/// triangle pointing down (first) or up (second).
+pub const VERTEX_NEIGHBORS: [[(i32, i32); 12]; 2] = [
+    [
+        (-1, -2), (-1, -1), (-1, 0), (-1, 1), (-1, 2),
+        (0, -2), (0, -1), (0, 1), (0, 2),
+        (1, -1), (1, 0), (1, 1),
+    ],
+    [
+        (-1, -1), (-1, 0), (-1, 1),
+        (0, -2), (0, -1), (0, 1), (0, 2),
+        (1, -2), (1, -1), (1, 0), (1, 1), (1, 2),
+    ],
+];
+
+/// Returns whether the triangle in a row and column points up.
+pub fn points_up(row: u32, column: u32) -> bool {
+    (row + column).is_multiple_of(2)
+}
+
+/// The height of a triangle with the given side length.
+fn triangle_height(size: f64) -> f64 {
+    3f64.sqrt() / 2.0 * size
+}
+
+/// The corners of the triangle in a row and column, with triangles of the
+/// given side length and the top-left corner of the drawing at the origin.
+pub fn corners(row: u32, column: u32, size: f64) -> [(f64, f64); 3] {
+    let left = column as f64 * size / 2.0;
+    let (middle, right) = (left + size / 2.0, left + size);
+    let top = row as f64 * triangle_height(size);
+    let bottom = top + triangle_height(size);
+
+    if points_up(row, column) {
+        [(middle, top), (right, bottom), (left, bottom)]
+    } else {
+        [(left, top), (right, top), (middle, bottom)]
+    }
+}
+
+/// The row and column of the triangle containing a point, in the layout
+/// of `corners`. The result may lie outside the universe.
+pub fn cell_at(x: f64, y: f64, size: f64) -> (i64, i64) {
+    let half = size / 2.0;
+    let row = (y / triangle_height(size)).floor() as i64;
+    let column = (x / half).floor() as i64;
+
+    // The triangle starting in this half column covers its left part up to
+    // the slanted edge, the one starting a half column earlier the rest.
+    let fx = x / half - column as f64;
+    let fy = y / triangle_height(size) - row as f64;
+    let inside = if (row + column).rem_euclid(2) == 0 { fy >= 1.0 - fx } else { fy <= fx };
+
+    (row, if inside { column } else { column - 1 })
+}
@@ -0,0 +1,158 @@
+//! Running two engines in lockstep, to check that an engine computes the
+//! same generations as a reference one.
+//!
+//! Both engines advance one generation at a time, and their live cells are
+//! compared after every generation. On the first difference, the cell and
+//! the neighbourhood it grew from are reported.
+
+use std::fmt;
+
+use crate::engine::Engine;
+use crate::error::Error;
+
+/// The number of cells reported on each side of a cell that differs.
+const RADIUS: i64 = 2;
+
+/// The first cell two engines disagree on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    /// The generation the engines disagree in, 0 if they hold different
+    /// cells to begin with.
+    pub generation: u64,
+    pub row: i64,
+    pub column: i64,
+    /// Whether the cell is alive in the reference engine.
+    pub expected: bool,
+    /// The 5×5 cells around the cell in the generation before, which the
+    /// engines agreed on, row by row. In generation 0, the cells of the
+    /// reference engine instead.
+    pub neighborhood: Vec<Vec<bool>>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = |alive: bool| if alive { "alive" } else { "dead" };
+        writeln!(
+            f,
+            "generation {}, row {}, column {}: expected {}, found {}",
+            self.generation,
+            self.row,
+            self.column,
+            state(self.expected),
+            state(!self.expected)
+        )?;
+        match self.generation.checked_sub(1) {
+            Some(generation) => writeln!(f, "the cells around it in generation {}:", generation)?,
+            None => writeln!(f, "the cells around it in the reference engine:")?,
+        }
+        for line in &self.neighborhood {
+            for &cell in line {
+                let symbol = if cell { '◼' } else { '◻' };
+                write!(f, "{}", symbol)?;
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Two engines advancing together, a reference one and a candidate.
+pub struct Lockstep {
+    reference: Box<dyn Engine>,
+    candidate: Box<dyn Engine>,
+    generation: u64,
+    // The live cells of the current generation, which both engines agree
+    // on, row by row.
+    cells: Vec<(i64, i64)>,
+}
+
+impl Lockstep {
+    /// Pair two engines, or fail with the first cell they disagree on in
+    /// generation 0 if they do not hold the same cells to begin with.
+    pub fn new(reference: Box<dyn Engine>, candidate: Box<dyn Engine>) -> Result<Lockstep, Divergence> {
+        let cells = reference.live_cells();
+        match first_difference(&cells, &candidate.live_cells()) {
+            Some((row, column)) => Err(divergence(&cells, 0, &cells, row, column)),
+            None => Ok(Lockstep { reference, candidate, generation: 0, cells }),
+        }
+    }
+
+    /// Get the number of generations both engines agreed on.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Get the reference engine.
+    pub fn reference(&self) -> &dyn Engine {
+        self.reference.as_ref()
+    }
+
+    /// Get the engine checked against the reference one.
+    pub fn candidate(&self) -> &dyn Engine {
+        self.candidate.as_ref()
+    }
+
+    /// Advance both engines by a number of generations, one at a time,
+    /// stopping at the first generation they disagree on. Fails if either
+    /// engine fails to advance.
+    pub fn run(&mut self, generations: u64) -> Result<Option<Divergence>, Error> {
+        for _ in 0..generations {
+            if let Some(divergence) = self.step()? {
+                return Ok(Some(divergence));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Advance both engines by one generation, returning where they
+    /// disagree if they do.
+    fn step(&mut self) -> Result<Option<Divergence>, Error> {
+        self.reference.step(1)?;
+        self.candidate.step(1)?;
+
+        let expected = self.reference.live_cells();
+        let found = self.candidate.live_cells();
+        let (row, column) = match first_difference(&expected, &found) {
+            Some(cell) => cell,
+            None => {
+                self.generation += 1;
+                self.cells = expected;
+                return Ok(None);
+            }
+        };
+
+        Ok(Some(divergence(&self.cells, self.generation + 1, &expected, row, column)))
+    }
+}
+
+/// Describe a cell two engines disagree on in a generation, from the cells
+/// of the generation before and the cells of the reference engine, each
+/// sorted row by row.
+fn divergence(before: &[(i64, i64)], generation: u64, expected: &[(i64, i64)], row: i64, column: i64) -> Divergence {
+    let neighborhood = (row - RADIUS..=row + RADIUS)
+        .map(|r| {
+            (column - RADIUS..=column + RADIUS)
+                .map(|c| before.binary_search(&(r, c)).is_ok())
+                .collect()
+        })
+        .collect();
+    Divergence {
+        generation,
+        row,
+        column,
+        expected: expected.binary_search(&(row, column)).is_ok(),
+        neighborhood,
+    }
+}
+
+/// Get the first cell, row by row, in only one of two sorted lists.
+fn first_difference(a: &[(i64, i64)], b: &[(i64, i64)]) -> Option<(i64, i64)> {
+    a.iter()
+        .zip(b.iter())
+        .find(|(x, y)| x != y)
+        .map(|(&x, &y)| x.min(y))
+        .or_else(|| a.get(b.len()).or_else(|| b.get(a.len())).cloned())
+}
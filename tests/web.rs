@@ -1,16 +1,47 @@
-//! Test suite for the Web and headless browsers.
-
-#![cfg(target_arch = "wasm32")]
+//! Test suite for the Web and headless browsers, which also runs natively
+//! with `cargo test`.
 
 extern crate wasm_bindgen_test;
 use wasm_bindgen_test::*;
 
 extern crate rust_wasm_game_of_life;
-use rust_wasm_game_of_life::Universe;
+use rust_wasm_game_of_life::{
+    config_bit, Algorithm, Anchor, ConfigValue, Backend, Edges, Engine, Error, Grid, Hashlife, Kernel, Lockstep, Neighborhood, Random, Rule, Soup, SparseUniverse, Symmetry, Topology, Universe,
+    UniverseBuilder, MAX_STEP_LOG2,
+};
 
 wasm_bindgen_test_configure!(run_in_browser);
 
-#[wasm_bindgen_test]
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+// Counts the allocations of every thread, to check that ticking does not
+// allocate.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+#[wasm_bindgen_test(unsupported = test)]
 fn pass() {
     assert_eq!(1 + 1, 2);
 }
@@ -18,22 +49,22 @@ fn pass() {
 #[cfg(test)]
 pub fn input_spaceship() -> Universe {
     let mut universe = Universe::new();
-    universe.set_width(6);
-    universe.set_height(6);
-    universe.set_cells(&[(1,2), (2,3), (3,1), (3,2), (3,3)]);
+    universe.set_width(6).unwrap();
+    universe.set_height(6).unwrap();
+    universe.set_cells(&[(1,2), (2,3), (3,1), (3,2), (3,3)]).unwrap();
     universe
 }
 
 #[cfg(test)]
 pub fn expected_spaceship() -> Universe {
     let mut universe = Universe::new();
-    universe.set_width(6);
-    universe.set_height(6);
-    universe.set_cells(&[(2,1), (2,3), (3,2), (3,3), (4,2)]);
+    universe.set_width(6).unwrap();
+    universe.set_height(6).unwrap();
+    universe.set_cells(&[(2,1), (2,3), (3,2), (3,3), (4,2)]).unwrap();
     universe
 }
 
-#[wasm_bindgen_test]
+#[wasm_bindgen_test(unsupported = test)]
 pub fn test_tick() {
     // Let's create a smaller Universe with a small spaceship to test!
     let mut input_universe = input_spaceship();
@@ -43,6 +74,1744 @@ pub fn test_tick() {
     let expected_universe = expected_spaceship();
 
     // Call 'tick' and then see if the cells in the 'Universe's are the same.
-    input_universe.tick();
+    input_universe.tick().unwrap();
     assert_eq!(&input_universe.get_cells(), &expected_universe.get_cells());
 }
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_rule_notations() {
+    let highlife: Rule = "B36/S23".parse().unwrap();
+    assert_eq!(highlife.to_string(), "B36/S23");
+    assert_eq!("S23/B36".parse::<Rule>().unwrap(), highlife);
+    assert_eq!("23/36".parse::<Rule>().unwrap(), highlife);
+    assert_eq!("b3/s23".parse::<Rule>().unwrap(), Rule::conway());
+
+    // Seeds has no survival conditions at all.
+    assert_eq!("/2".parse::<Rule>().unwrap().to_string(), "B2/S");
+
+    assert!("B9/S23".parse::<Rule>().is_err());
+    assert!("B3".parse::<Rule>().is_err());
+    assert!("B3/23".parse::<Rule>().is_err());
+    assert!("B3/S2/S3".parse::<Rule>().is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_tick_with_rule() {
+    // Under Seeds (B2/S), a domino explodes into two dominoes on either side.
+    let mut universe = Universe::new();
+    universe.set_width(6).unwrap();
+    universe.set_height(6).unwrap();
+    universe.set_cells(&[(2,2), (2,3)]).unwrap();
+    universe.set_rule("B2/S").unwrap();
+
+    let mut expected_universe = Universe::new();
+    expected_universe.set_width(6).unwrap();
+    expected_universe.set_height(6).unwrap();
+    expected_universe.set_cells(&[(1,2), (1,3), (3,2), (3,3)]).unwrap();
+
+    universe.tick().unwrap();
+    assert_eq!(&universe.get_cells(), &expected_universe.get_cells());
+
+    assert!(universe.set_rule("B3/S23/X").is_err());
+    assert_eq!(universe.rule(), "B2/S");
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_generations_rule() {
+    let brians_brain: Rule = "B2/S/C3".parse().unwrap();
+    assert_eq!(brians_brain.states(), 3);
+    assert_eq!("345/2/4".parse::<Rule>().unwrap().to_string(), "B2/S345/C4");
+    assert!("B2/S/C1".parse::<Rule>().is_err());
+    assert!("B2/S/3".parse::<Rule>().is_err());
+
+    let mut universe = Universe::new();
+    universe.set_width(6).unwrap();
+    universe.set_height(6).unwrap();
+    universe.set_cells(&[(2,2), (2,3)]).unwrap();
+    universe.set_rule("B2/S/C3").unwrap();
+    assert_eq!(universe.num_states(), 3);
+
+    // The domino starts dying while it gives birth on both sides.
+    universe.tick().unwrap();
+    let states = universe.get_cell_states();
+    assert_eq!((states[2 * 6 + 2], states[2 * 6 + 3]), (2, 2));
+    assert_eq!((states[6 + 2], states[6 + 3]), (1, 1));
+    assert_eq!((states[3 * 6 + 2], states[3 * 6 + 3]), (1, 1));
+
+    // Toggling a dying cell kills it, toggling a dead cell brings it to life.
+    universe.toggle_cell(2, 2).unwrap();
+    universe.toggle_cell(0, 0).unwrap();
+    let states = universe.get_cell_states();
+    assert_eq!((states[2 * 6 + 2], states[0]), (0, 1));
+
+    universe.set_rule("B3/S23").unwrap();
+    assert!(universe.get_cell_states().is_empty());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_larger_than_life_rule() {
+    let bosco: Rule = "R5,C0,M1,S34..58,B34..45,NM".parse().unwrap();
+    assert_eq!(bosco.range(), 5);
+    assert_eq!(bosco.neighborhood(), Neighborhood::Moore);
+    assert!(bosco.include_center());
+    assert_eq!(bosco.to_string(), "R5,C0,M1,S34..58,B34..45,NM");
+
+    assert_eq!("r2,c3,m0,s1..4,b3,nn".parse::<Rule>().unwrap().to_string(), "R2,C3,M0,S1..4,B3..3,NN");
+    assert_eq!("R1,C0,M0,S2..3,B3..3,NM".parse::<Rule>().unwrap(), Rule::conway());
+
+    assert!("R0,C0,M0,S1..2,B1..2,NM".parse::<Rule>().is_err());
+    assert!("R1,C0,M0,S1..9,B1..2,NM".parse::<Rule>().is_err());
+    assert!("R2,C0,M0,S3..1,B1..2,NM".parse::<Rule>().is_err());
+    assert!("R2,C0,M0,B1..2,NM".parse::<Rule>().is_err());
+}
+
+/// Fill a universe with a reproducible pseudo-random pattern.
+#[cfg(test)]
+fn pseudo_random_cells(width: u32, height: u32, seed: u32) -> Vec<(u32, u32)> {
+    let mut state = seed;
+    let mut cells = Vec::new();
+    for row in 0..height {
+        for col in 0..width {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            if (state >> 16).is_multiple_of(3) {
+                cells.push((row, col));
+            }
+        }
+    }
+    cells
+}
+
+#[cfg(test)]
+fn pseudo_random_universe(width: u32, height: u32, seed: u32) -> Universe {
+    let mut universe = Universe::new();
+    universe.set_width(width).unwrap();
+    universe.set_height(height).unwrap();
+    universe.set_cells(&pseudo_random_cells(width, height, seed)).unwrap();
+    universe
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_tick_larger_than_life() {
+    for rule in ["R2,C0,M0,S5..9,B6..8,NM", "R3,C0,M1,S8..14,B7..10,NN", "R2,C0,M1,S6..10,B5..7,NH"].iter() {
+        let rule: Rule = rule.parse().unwrap();
+        let (width, height) = (20, 17);
+        let mut universe = pseudo_random_universe(width, height, 7);
+        universe.replace_rule(rule.clone()).unwrap();
+
+        // Count every neighbourhood cell by cell on the torus.
+        let range = rule.range() as i32;
+        let alive = |cells: &[u32], row: i32, col: i32| {
+            let idx = (row.rem_euclid(height as i32) * width as i32 + col.rem_euclid(width as i32)) as usize;
+            cells[idx / 32] & (1 << (idx % 32)) != 0
+        };
+        let cells = universe.get_cells().to_vec();
+        let mut expected = Vec::new();
+        for row in 0..height as i32 {
+            for col in 0..width as i32 {
+                let mut count = 0;
+                for dy in -range..=range {
+                    for dx in -range..=range {
+                        let inside = match rule.neighborhood() {
+                            Neighborhood::Moore => true,
+                            Neighborhood::VonNeumann => dx.abs() + dy.abs() <= range,
+                            Neighborhood::Hexagonal => (dx - dy).abs() <= range,
+                            _ => unreachable!(),
+                        };
+                        let center = dx == 0 && dy == 0 && !rule.include_center();
+                        if inside && !center && alive(&cells, row + dy, col + dx) {
+                            count += 1;
+                        }
+                    }
+                }
+                if rule.next_state(alive(&cells, row, col), count) {
+                    expected.push((row as u32, col as u32));
+                }
+            }
+        }
+
+        let mut expected_universe = Universe::new();
+        expected_universe.set_width(width).unwrap();
+        expected_universe.set_height(height).unwrap();
+        expected_universe.set_cells(&expected).unwrap();
+
+        universe.tick().unwrap();
+        assert_eq!(&universe.get_cells(), &expected_universe.get_cells());
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_isotropic_rule() {
+    let rule: Rule = "B2-a/S12".parse().unwrap();
+    assert!(!rule.is_totalistic());
+    assert_eq!(rule.to_string(), "B2-a/S12");
+    assert_eq!("b3/s2-I34Q".parse::<Rule>().unwrap().to_string(), "B3/S2-i34q");
+    assert_eq!("B2ceikn/S1ce2/C3".parse::<Rule>().unwrap().to_string(), "B2-a/S12/C3");
+
+    // Naming every letter of a count is the same as the bare count.
+    assert_eq!("B3aceijknqry/S2acekin3".parse::<Rule>().unwrap(), Rule::conway());
+
+    assert!("B2x/S23".parse::<Rule>().is_err());
+    assert!("B1a/S23".parse::<Rule>().is_err());
+    assert!("B0c/S23".parse::<Rule>().is_err());
+    assert!("B3-/S23".parse::<Rule>().is_err());
+
+    // The letters of a count split its arrangements without overlapping.
+    let births = |rule: &str| {
+        let rule: Rule = rule.parse().unwrap();
+        (0..512).filter(|&config| rule.next_state_from_config(config)).count()
+    };
+    for count in 1..8 {
+        let all = births(&format!("B{}/S", count));
+        let letters = "aceijknqrtwyz"
+            .chars()
+            .filter_map(|letter| format!("B{}{}/S", count, letter).parse::<Rule>().ok())
+            .map(|rule| births(&rule.to_string()))
+            .sum::<usize>();
+        assert_eq!(letters, all);
+    }
+
+    // 2a is a corner next to an edge, in any rotation or reflection.
+    let rule: Rule = "B2a/S".parse().unwrap();
+    assert!(rule.next_state_from_config(config_bit(-1, 0) | config_bit(-1, -1)));
+    assert!(rule.next_state_from_config(config_bit(1, 1) | config_bit(0, 1)));
+    assert!(!rule.next_state_from_config(config_bit(-1, 0) | config_bit(1, 0)));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_tick_isotropic() {
+    // Both cells next to the two live cells see them on two orthogonal
+    // edges, which is the 2e arrangement.
+    let mut universe = Universe::new();
+    universe.set_width(6).unwrap();
+    universe.set_height(6).unwrap();
+    universe.set_cells(&[(1,2), (2,3)]).unwrap();
+    universe.set_rule("B2e/S").unwrap();
+
+    let mut expected_universe = Universe::new();
+    expected_universe.set_width(6).unwrap();
+    expected_universe.set_height(6).unwrap();
+    expected_universe.set_cells(&[(1,3), (2,2)]).unwrap();
+
+    universe.tick().unwrap();
+    assert_eq!(&universe.get_cells(), &expected_universe.get_cells());
+
+    universe.set_rule("B2-e/S").unwrap();
+    universe.tick().unwrap();
+    assert!(universe.get_cells().iter().all(|&block| block == 0));
+}
+
+const CONWAY_MAP: &str =
+    "MAPARYXfhZofugWaH7oaIDogBZofuhogOiAaIDogIAAgAAWaH7oaIDogGiA6ICAAIAAaIDogIAAgACAAIAAAAAAAA";
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_map_rule() {
+    assert_eq!(Rule::conway().to_map_string().unwrap(), CONWAY_MAP);
+    assert_eq!(CONWAY_MAP.parse::<Rule>().unwrap(), Rule::conway());
+    assert_eq!(format!("{}==", CONWAY_MAP).parse::<Rule>().unwrap(), Rule::conway());
+    assert_eq!(format!("{}/3", CONWAY_MAP).parse::<Rule>().unwrap().to_string(), "B3/S23/C3");
+
+    // Equivalent rules in different notations have the same MAP string.
+    let hensel: Rule = "B3aceijknqry/S2-a2a3".parse().unwrap();
+    assert_eq!(hensel.to_map_string(), Rule::conway().to_map_string());
+    let isotropic: Rule = "B2-a/S12".parse().unwrap();
+    let isotropic_map = isotropic.to_map_string().unwrap();
+    assert_eq!(isotropic_map.parse::<Rule>().unwrap().to_string(), "B2-a/S12");
+    assert_eq!("R2,C0,M0,S2..3,B3..3,NM".parse::<Rule>().unwrap().to_map_string(), None);
+
+    // A rule that is not isotropic can only be printed as a MAP string.
+    let mut map = [false; 512];
+    for (config, next) in map.iter_mut().enumerate() {
+        *next = config & config_bit(0, -1) != 0;
+    }
+    let shift = Rule::from_map(map);
+    let shift_map = shift.to_map_string().unwrap();
+    assert_eq!(shift.to_string(), shift_map);
+    assert_eq!(shift_map.parse::<Rule>().unwrap(), shift);
+
+    assert!("MAPARYX".parse::<Rule>().is_err());
+    assert!(format!("{}!", CONWAY_MAP).parse::<Rule>().is_err());
+    assert!(CONWAY_MAP.replace('f', "*").parse::<Rule>().is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_tick_map() {
+    // Every cell takes the state of its western neighbour, so the whole
+    // pattern moves east by one cell each generation.
+    let mut map = [false; 512];
+    for (config, next) in map.iter_mut().enumerate() {
+        *next = config & config_bit(0, -1) != 0;
+    }
+
+    let mut universe = input_spaceship();
+    universe.set_rule(&Rule::from_map(map).to_string()).unwrap();
+
+    let mut expected_universe = Universe::new();
+    expected_universe.set_width(6).unwrap();
+    expected_universe.set_height(6).unwrap();
+    expected_universe.set_cells(&[(1,3), (2,4), (3,2), (3,3), (3,4)]).unwrap();
+
+    universe.tick().unwrap();
+    assert_eq!(&universe.get_cells(), &expected_universe.get_cells());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_b0_rule() {
+    let rule: Rule = "B0/S8".parse().unwrap();
+    assert!(rule.has_b0());
+    assert!(rule.next_background(false));
+    assert!(rule.next_background(true));
+
+    let rule: Rule = "B03/S23".parse().unwrap();
+    assert!(rule.next_background(false));
+    assert!(!rule.next_background(true));
+    assert!(!Rule::conway().has_b0());
+
+    assert!("B0/S23/C3".parse::<Rule>().is_err());
+    assert!("R2,C0,M0,S1..3,B0..2,NM".parse::<Rule>().is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_tick_b0() {
+    let (width, height) = (12, 10);
+    for rule in ["B03/S23", "B0123478/S34678"].iter() {
+        let rule: Rule = rule.parse().unwrap();
+        let mut universe = pseudo_random_universe(width, height, 3);
+        universe.replace_rule(rule.clone()).unwrap();
+
+        // Follow the actual states of the cells, with an alive background
+        // filled in, and compare them to the universe generation by
+        // generation.
+        let alive = |universe: &Universe, row: u32, col: u32| {
+            let idx = (row * width + col) as usize;
+            (universe.get_cells()[idx / 32] & (1 << (idx % 32)) != 0) != universe.background()
+        };
+        let mut cells: Vec<bool> = (0..height)
+            .flat_map(|row| (0..width).map(move |col| (row, col)))
+            .map(|(row, col)| alive(&universe, row, col))
+            .collect();
+
+        for generation in 0..4 {
+            let mut next = Vec::new();
+            for row in 0..height {
+                for col in 0..width {
+                    let mut config = 0;
+                    for dy in -1..=1 {
+                        for dx in -1..=1 {
+                            let r = (row as i32 + dy).rem_euclid(height as i32) as u32;
+                            let c = (col as i32 + dx).rem_euclid(width as i32) as u32;
+                            if cells[(r * width + c) as usize] {
+                                config |= config_bit(dy, dx);
+                            }
+                        }
+                    }
+                    next.push(rule.next_state_from_config(config));
+                }
+            }
+            cells = next;
+
+            universe.tick().unwrap();
+            assert_eq!(universe.background(), generation % 2 == 0 || rule.to_string().ends_with('8'));
+            for row in 0..height {
+                for col in 0..width {
+                    assert_eq!(alive(&universe, row, col), cells[(row * width + col) as usize]);
+                }
+            }
+        }
+
+        // Going back to a rule without B0 fills in the background.
+        universe.replace_rule(Rule::conway()).unwrap();
+        assert!(!universe.background());
+        for row in 0..height {
+            for col in 0..width {
+                assert_eq!(alive(&universe, row, col), cells[(row * width + col) as usize]);
+            }
+        }
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_hexagonal_rule() {
+    let rule: Rule = "B2/S34H".parse().unwrap();
+    assert_eq!(rule.neighborhood(), Neighborhood::Hexagonal);
+    assert_eq!(rule, Rule::from_hex_counts(&[2], &[3, 4]).unwrap());
+    assert_eq!(rule.to_string(), "B2/S34H");
+    assert_eq!("34/2h".parse::<Rule>().unwrap(), rule);
+    assert_eq!("R1,C0,M0,S3..4,B2..2,NH".parse::<Rule>().unwrap(), rule);
+    assert_eq!("B2/S34H/C3".parse::<Rule>().unwrap().to_string(), "B2/S34H/C3");
+
+    // The north-east and south-west cells are not neighbours.
+    assert!(rule.next_state_from_config(config_bit(0, -1) | config_bit(0, 1)));
+    assert!(!rule.next_state_from_config(config_bit(-1, 1) | config_bit(1, -1)));
+
+    // The same rule given as a MAP string is recognised as hexagonal.
+    assert_eq!(rule.to_map_string().unwrap().parse::<Rule>().unwrap(), rule);
+
+    assert!("B7/S34H".parse::<Rule>().is_err());
+    assert!("B2a/S34H".parse::<Rule>().is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_tick_hexagonal() {
+    let rule: Rule = "B2/S34H".parse().unwrap();
+    let (width, height) = (9, 8);
+    let mut universe = pseudo_random_universe(width, height, 5);
+    universe.replace_rule(rule.clone()).unwrap();
+
+    // The 6 neighbours in axial coordinates.
+    let offsets = [(-1, -1), (-1, 0), (0, -1), (0, 1), (1, 0), (1, 1)];
+    let alive = |cells: &[u32], row: i32, col: i32| {
+        let idx = (row.rem_euclid(height as i32) * width as i32 + col.rem_euclid(width as i32)) as usize;
+        cells[idx / 32] & (1 << (idx % 32)) != 0
+    };
+    let cells = universe.get_cells().to_vec();
+    let mut expected = Vec::new();
+    for row in 0..height as i32 {
+        for col in 0..width as i32 {
+            let count = offsets.iter().filter(|&&(dy, dx)| alive(&cells, row + dy, col + dx)).count();
+            if rule.next_state(alive(&cells, row, col), count as u32) {
+                expected.push((row as u32, col as u32));
+            }
+        }
+    }
+
+    let mut expected_universe = Universe::new();
+    expected_universe.set_width(width).unwrap();
+    expected_universe.set_height(height).unwrap();
+    expected_universe.set_cells(&expected).unwrap();
+
+    universe.tick().unwrap();
+    assert_eq!(&universe.get_cells(), &expected_universe.get_cells());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_hex_coordinates() {
+    let mut universe = Universe::new();
+    universe.set_width(7).unwrap();
+    universe.set_height(6).unwrap();
+    universe.set_rule("B2/S34H").unwrap();
+    assert!(universe.is_hexagonal());
+
+    let size = 10.0;
+    let center = |row: u32, col: u32| (universe.hex_center_x(row, col, size), universe.hex_center_y(row, size));
+
+    // Every cell is hit at its centre and near its corners.
+    for row in 0..6 {
+        for col in 0..7 {
+            let idx = Some(row * 7 + col);
+            let (x, y) = center(row, col);
+            assert_eq!(universe.hex_cell_at(x, y, size), idx);
+            for i in 0..6 {
+                let angle = std::f64::consts::PI / 3.0 * i as f64 + 0.5;
+                let (dx, dy) = (0.8 * size * angle.cos(), 0.8 * size * angle.sin());
+                assert_eq!(universe.hex_cell_at(x + dx, y + dy, size), idx);
+            }
+        }
+    }
+
+    // Neighbours that do not wrap around are drawn next to each other.
+    let (x, y) = center(3, 3);
+    for &(row, col) in [(2, 2), (2, 3), (3, 2), (3, 4), (4, 3), (4, 4)].iter() {
+        let (nx, ny) = center(row, col);
+        let distance = ((nx - x).powi(2) + (ny - y).powi(2)).sqrt();
+        assert!((distance - 3f64.sqrt() * size).abs() < 1e-9);
+    }
+
+    assert_eq!(universe.hex_cell_at(-5.0, -5.0, size), None);
+    assert_eq!(universe.hex_cell_at(0.0, 1000.0, size), None);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_triangular_rule() {
+    let rule: Rule = "B4/S56L".parse().unwrap();
+    assert_eq!(rule.neighborhood(), Neighborhood::TriangularVertices);
+    assert_eq!(rule, Rule::from_triangular_counts(Neighborhood::TriangularVertices, &[4], &[5, 6]).unwrap());
+    assert_eq!(rule.to_string(), "B4/S56L");
+    assert_eq!("56/4l".parse::<Rule>().unwrap(), rule);
+    assert_eq!("B4/S5abL".parse::<Rule>().unwrap().to_string(), "B4/S5ABL");
+    assert_eq!("B4/S56L/C3".parse::<Rule>().unwrap().to_string(), "B4/S56L/C3");
+
+    let rule: Rule = "b1/s12le".parse().unwrap();
+    assert_eq!(rule.neighborhood(), Neighborhood::TriangularEdges);
+    assert_eq!(rule.to_string(), "B1/S12LE");
+    assert!(!rule.is_life_like());
+
+    assert!("B0/S1LE".parse::<Rule>().is_err());
+    assert!("B4/S4LE".parse::<Rule>().is_err());
+    assert!("B4/SDL".parse::<Rule>().is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_tick_triangular() {
+    let (width, height) = (10, 8);
+    let size = 6.0;
+    let geometry = Universe::new();
+
+    // Find the neighbours of a triangle pointing each way from where the
+    // triangles are drawn: those sharing two corners share an edge.
+    let neighbors = |row: u32, col: u32, shared: usize| {
+        let corners = geometry.triangle_corners(row, col, size);
+        let mut offsets = Vec::new();
+        for dy in -1..=1i32 {
+            for dx in -2..=2i32 {
+                let other = geometry.triangle_corners((row as i32 + dy) as u32, (col as i32 + dx) as u32, size);
+                let common = (0..3)
+                    .filter(|&i| (0..3).any(|j| (corners[2 * i] - other[2 * j]).abs() < 1e-9 && (corners[2 * i + 1] - other[2 * j + 1]).abs() < 1e-9))
+                    .count();
+                if (dy, dx) != (0, 0) && common >= shared {
+                    offsets.push((dy, dx));
+                }
+            }
+        }
+        offsets
+    };
+
+    for &(rule, shared, size) in [("B1/S12LE", 2, 3), ("B4/S56L", 1, 12)].iter() {
+        let rule: Rule = rule.parse().unwrap();
+        let up = neighbors(4, 4, shared);
+        let down = neighbors(4, 5, shared);
+        assert!(geometry.triangle_points_up(4, 4) && !geometry.triangle_points_up(4, 5));
+        assert_eq!((up.len(), down.len()), (size, size));
+
+        let mut universe = pseudo_random_universe(width, height, 11);
+        universe.replace_rule(rule.clone()).unwrap();
+
+        let alive = |cells: &[u32], row: i32, col: i32| {
+            let idx = (row.rem_euclid(height as i32) * width as i32 + col.rem_euclid(width as i32)) as usize;
+            cells[idx / 32] & (1 << (idx % 32)) != 0
+        };
+        let cells = universe.get_cells().to_vec();
+        let mut expected = Vec::new();
+        for row in 0..height as i32 {
+            for col in 0..width as i32 {
+                let offsets = if (row + col) % 2 == 0 { &up } else { &down };
+                let count = offsets.iter().filter(|&&(dy, dx)| alive(&cells, row + dy, col + dx)).count();
+                if rule.next_state(alive(&cells, row, col), count as u32) {
+                    expected.push((row as u32, col as u32));
+                }
+            }
+        }
+
+        let mut expected_universe = Universe::new();
+        expected_universe.set_width(width).unwrap();
+        expected_universe.set_height(height).unwrap();
+        expected_universe.set_cells(&expected).unwrap();
+
+        universe.tick().unwrap();
+        assert_eq!(&universe.get_cells(), &expected_universe.get_cells());
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_triangle_coordinates() {
+    let mut universe = Universe::new();
+    universe.set_width(8).unwrap();
+    universe.set_height(6).unwrap();
+    universe.set_rule("B4/S56L").unwrap();
+    assert!(universe.is_triangular());
+
+    // Every cell is hit at its centroid and near its corners.
+    let size = 10.0;
+    for row in 0..6 {
+        for col in 0..8 {
+            let idx = Some(row * 8 + col);
+            let corners = universe.triangle_corners(row, col, size);
+            let x = (corners[0] + corners[2] + corners[4]) / 3.0;
+            let y = (corners[1] + corners[3] + corners[5]) / 3.0;
+            assert_eq!(universe.triangle_cell_at(x, y, size), idx);
+            for i in 0..3 {
+                let (cx, cy) = (corners[2 * i], corners[2 * i + 1]);
+                assert_eq!(universe.triangle_cell_at(cx + 0.9 * (x - cx), cy + 0.9 * (y - cy), size), idx);
+            }
+        }
+    }
+
+    assert_eq!(universe.triangle_cell_at(-1.0, 5.0, size), None);
+    assert_eq!(universe.triangle_cell_at(1.0, 1000.0, size), None);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_kernel() {
+    let cross = Kernel::from_mask(
+        "..#..
+         ..#..
+         ##.##
+         ..#..
+         ..#..",
+    )
+    .unwrap();
+    assert_eq!(cross, Kernel::cross(2));
+    assert_eq!(cross.range(), 2);
+    assert_eq!(cross.total_weight(), 8);
+    assert_eq!(cross.weight(0, -2), 1);
+    assert_eq!(cross.weight(1, 1), 0);
+
+    let von_neumann = Kernel::from_offsets(&[(0, -1), (-1, 0), (1, 0), (0, 1)]).unwrap();
+    assert_eq!(von_neumann, Kernel::von_neumann(1));
+    assert_eq!(von_neumann, Kernel::cross(1));
+
+    // Cells outside a smaller kernel do not widen its range.
+    let weighted = Kernel::from_mask(".....\n.121.\n.2.2.\n.121.\n.....").unwrap();
+    assert_eq!(weighted.range(), 1);
+    assert_eq!(weighted.total_weight(), 12);
+    assert!(!weighted.is_unweighted());
+
+    assert_eq!(Kernel::from_hex_mask(2, &cross.to_hex_mask()).unwrap(), cross);
+    assert_eq!(Kernel::from_hex_weights(1, &weighted.to_hex_weights()).unwrap(), weighted);
+
+    assert_eq!(Kernel::from_mask("##\n##").unwrap_err().code(), "ParseError");
+    assert_eq!(Kernel::from_mask("#.#\n.x.\n#.#").unwrap_err().code(), "ParseError");
+    assert_eq!(Kernel::from_hex_mask(1, "F").unwrap_err().code(), "ParseError");
+    assert_eq!(Kernel::from_offsets(&[(0, 51)]).unwrap_err().code(), "InvalidArgument");
+    assert_eq!(Kernel::from_weights(&[(1, 0, 9), (1, 0, 7)]).unwrap_err().code(), "InvalidArgument");
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_custom_rule() {
+    // The cross is written as a bit mask without the middle cell.
+    let rule = Rule::from_kernel(Kernel::cross(2), &[3], &[2, 3]).unwrap();
+    assert_eq!(rule.neighborhood(), Neighborhood::Custom);
+    assert_eq!(rule.to_string(), "R2,C0,M0,S2..3,B3..3,N@213C84");
+    assert_eq!(rule.to_string().parse::<Rule>().unwrap(), rule);
+
+    let weighted = Kernel::from_weights(&[(-1, 0, 2), (1, 0, 2), (0, -1, 1), (0, 1, 1), (0, 0, 3)]).unwrap();
+    let rule = Rule::from_kernel(weighted, &[2, 3, 6], &[3, 4]).unwrap();
+    assert_eq!(rule.to_string(), "R1,C0,M0,S3..4,B2..3,6..6,NW010232010");
+    assert_eq!(rule.to_string().parse::<Rule>().unwrap(), rule);
+
+    // Kernels of the other neighbourhoods give the usual rules.
+    assert_eq!(Rule::from_kernel(Kernel::moore(1), &[3], &[2, 3]).unwrap(), Rule::conway());
+    assert_eq!(
+        Rule::from_kernel(Kernel::from_offsets(&[(0, 0), (0, 1), (1, 0), (0, -1), (-1, 0)]).unwrap(), &[2], &[3]).unwrap(),
+        "R1,C0,M1,S3..3,B2..2,NN".parse().unwrap()
+    );
+    assert_eq!(Rule::from_kernel(Kernel::hexagonal(1), &[2], &[3, 4]).unwrap(), "B2/S34H".parse().unwrap());
+    assert_eq!("R1,C0,M0,S2..3,B3..3,N@FF".parse::<Rule>().unwrap(), Rule::conway());
+
+    assert!("R2,C0,M0,S2..3,B3..3,N@213C8".parse::<Rule>().is_err());
+    assert!("R2,C0,M0,S2..9,B3..3,N@213C84".parse::<Rule>().is_err());
+    assert!("R1,C0,M0,S2..3,B3..3,NX".parse::<Rule>().is_err());
+
+    // Counts that Larger than Life notation cannot write are refused.
+    assert!(Rule::from_kernel(Kernel::cross(2), &[], &[2, 3]).is_err());
+    assert!(Rule::from_kernel(Kernel::cross(2), &[3], &[9]).is_err());
+    #[allow(clippy::reversed_empty_ranges)]
+    let empty = 5..=3;
+    assert!(Rule::larger_than_life(2, Neighborhood::Moore, false, empty, 3..=4).is_err());
+    assert!(Rule::larger_than_life(2, Neighborhood::Moore, false, 2..=3, 3..=25).is_err());
+    assert!(Rule::larger_than_life(2, Neighborhood::Moore, false, 2..=3, 0..=4).is_err());
+    assert!(Rule::from_kernel(Kernel::cross(2), &[0, 3], &[2, 3]).is_err());
+    assert_eq!(Rule::from_kernel(Kernel::moore(1), &[0, 3], &[2, 3]).unwrap(), "B03/S23".parse().unwrap());
+    let rule = Rule::larger_than_life(2, Neighborhood::Moore, false, 5..=7, 3..=4).unwrap();
+    assert_eq!(rule.to_string(), "R2,C0,M0,S5..7,B3..4,NM");
+    assert_eq!(rule.to_string().parse::<Rule>().unwrap(), rule);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_tick_custom() {
+    let kernel = Kernel::from_mask(
+        "..1..
+         .2.2.
+         1.3.1
+         .2.2.
+         ..1..",
+    )
+    .unwrap();
+    let rule = Rule::from_kernel(kernel.clone(), &[3, 4, 5], &[5, 6, 7, 8]).unwrap();
+    let (width, height) = (11, 9);
+    let mut universe = pseudo_random_universe(width, height, 13);
+    universe.replace_rule(rule.clone()).unwrap();
+
+    let alive = |cells: &[u32], row: i32, col: i32| {
+        let idx = (row.rem_euclid(height as i32) * width as i32 + col.rem_euclid(width as i32)) as usize;
+        cells[idx / 32] & (1 << (idx % 32)) != 0
+    };
+    let cells = universe.get_cells().to_vec();
+    let mut expected = Vec::new();
+    for row in 0..height as i32 {
+        for col in 0..width as i32 {
+            let mut count = 0;
+            for dy in -2..=2 {
+                for dx in -2..=2 {
+                    if alive(&cells, row + dy, col + dx) {
+                        count += kernel.weight(dx, dy);
+                    }
+                }
+            }
+            if rule.next_state(alive(&cells, row, col), count) {
+                expected.push((row as u32, col as u32));
+            }
+        }
+    }
+
+    let mut expected_universe = Universe::new();
+    expected_universe.set_width(width).unwrap();
+    expected_universe.set_height(height).unwrap();
+    expected_universe.set_cells(&expected).unwrap();
+
+    universe.tick().unwrap();
+    assert_eq!(&universe.get_cells(), &expected_universe.get_cells());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_topology_spec() {
+    for &spec in ["T64,64", "T64,48+2", "T64-3,48", "P30,20", "K64*,64", "K64,32*", "C64,64", "S64"].iter() {
+        let (topology, width, height) = Topology::parse(spec).unwrap();
+        assert_eq!(topology.to_spec(width, height), spec);
+    }
+
+    assert_eq!(Topology::parse("t10,20+1").unwrap(), (Topology::ShiftedTorus { edges: Edges::Vertical, shift: 1 }, 10, 20));
+    assert_eq!(Topology::parse("K10*,20").unwrap().0, Topology::KleinBottle { twisted: Edges::Horizontal });
+
+    assert!(Topology::parse("T64").is_err());
+    assert!(Topology::parse("T64+1,64+1").is_err());
+    assert!(Topology::parse("K64,64").is_err());
+    assert!(Topology::parse("P64*,64").is_err());
+    assert!(Topology::parse("S64,64").is_err());
+    assert!(Topology::parse("X64,64").is_err());
+    assert!(Topology::parse("T0,64").is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_topology_wrap() {
+    let (w, h) = (5, 4);
+    assert_eq!(Topology::Torus.wrap(-1, 5, w, h), Some((3, 0)));
+    assert_eq!(Topology::Plane.wrap(-1, 2, w, h), None);
+    assert_eq!(Topology::Plane.wrap(3, 4, w, h), Some((3, 4)));
+
+    let shifted = Topology::ShiftedTorus { edges: Edges::Horizontal, shift: 2 };
+    assert_eq!(shifted.wrap(4, 1, w, h), Some((0, 3)));
+    assert_eq!(shifted.wrap(-1, 1, w, h), Some((3, 4)));
+    assert_eq!(shifted.wrap(1, 5, w, h), Some((1, 0)));
+
+    let klein = Topology::KleinBottle { twisted: Edges::Horizontal };
+    assert_eq!(klein.wrap(-1, 0, w, h), Some((3, 4)));
+    assert_eq!(klein.wrap(1, -1, w, h), Some((1, 4)));
+    assert_eq!(Topology::CrossSurface.wrap(2, 5, w, h), Some((1, 0)));
+    assert_eq!(Topology::CrossSurface.wrap(4, 1, w, h), Some((0, 3)));
+
+    // The top edge of a sphere is joined to the left edge, the bottom edge
+    // to the right edge, and the corners have no neighbours across them.
+    assert_eq!(Topology::Sphere.wrap(-1, 2, 4, 4), Some((2, 0)));
+    assert_eq!(Topology::Sphere.wrap(2, -1, 4, 4), Some((0, 2)));
+    assert_eq!(Topology::Sphere.wrap(4, 1, 4, 4), Some((1, 3)));
+    assert_eq!(Topology::Sphere.wrap(1, 4, 4, 4), Some((3, 1)));
+    assert_eq!(Topology::Sphere.wrap(-1, -1, 4, 4), None);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_tick_topology() {
+    // A blinker along the top edge of a plane loses the cell it would grow
+    // beyond the edge and dies out, while on a torus it keeps blinking.
+    let mut universe = Universe::new();
+    universe.set_topology("P6,6").unwrap();
+    universe.set_cells(&[(0, 2), (0, 3), (0, 4)]).unwrap();
+    universe.tick().unwrap();
+    let mut expected = Universe::new();
+    expected.set_topology("P6,6").unwrap();
+    expected.set_cells(&[(0, 3), (1, 3)]).unwrap();
+    assert_eq!(&universe.get_cells(), &expected.get_cells());
+    universe.tick().unwrap();
+    expected.reset_all_dead();
+    assert_eq!(&universe.get_cells(), &expected.get_cells());
+
+    // Every way of counting neighbours agrees with counting them one by
+    // one across the edges.
+    let (width, height) = (9, 9);
+    for &spec in ["T9,9+2", "P9,9", "K9*,9", "K9,9*", "C9,9", "S9"].iter() {
+        let (topology, ..) = Topology::parse(spec).unwrap();
+        for rule in ["B3/S23", "B2a/S12", "R2,C0,M1,S6..10,B5..7,NN", "R2,C0,M0,S2..3,B3..3,N@213C84"].iter() {
+            let rule: Rule = rule.parse().unwrap();
+            let mut universe = pseudo_random_universe(width, height, 17);
+            universe.set_topology(spec).unwrap();
+            assert_eq!(universe.get_topology(), topology);
+            let cells = universe.get_cells().to_vec();
+            universe.replace_rule(rule.clone()).unwrap();
+
+            let alive = |row: i64, col: i64| match topology.wrap(row, col, width, height) {
+                Some((r, c)) => cells[((r * width + c) / 32) as usize] & (1 << ((r * width + c) % 32)) != 0,
+                None => false,
+            };
+            let mut expected = Vec::new();
+            for row in 0..height as i64 {
+                for col in 0..width as i64 {
+                    let next = if rule.is_life_like() {
+                        let mut config = 0;
+                        for dy in -1..=1 {
+                            for dx in -1..=1 {
+                                if alive(row + dy, col + dx) {
+                                    config |= config_bit(dy as i32, dx as i32);
+                                }
+                            }
+                        }
+                        rule.next_state_from_config(config)
+                    } else {
+                        let range = rule.range() as i64;
+                        let mut count = 0;
+                        for dy in -range..=range {
+                            for dx in -range..=range {
+                                let weight = match rule.kernel() {
+                                    Some(kernel) => kernel.weight(dx as i32, dy as i32),
+                                    None if (dx, dy) == (0, 0) => rule.include_center() as u32,
+                                    None => (dx.abs() + dy.abs() <= range) as u32,
+                                };
+                                if alive(row + dy, col + dx) {
+                                    count += weight;
+                                }
+                            }
+                        }
+                        rule.next_state(alive(row, col), count)
+                    };
+                    if next {
+                        expected.push((row as u32, col as u32));
+                    }
+                }
+            }
+
+            let mut expected_universe = Universe::new();
+            expected_universe.set_width(width).unwrap();
+            expected_universe.set_height(height).unwrap();
+            expected_universe.set_cells(&expected).unwrap();
+
+            universe.tick().unwrap();
+            assert_eq!(&universe.get_cells(), &expected_universe.get_cells(), "{} on {}", rule, spec);
+        }
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_stamp_topology() {
+    // A glider inserted in the corner of a torus wraps around the edges.
+    let mut universe = Universe::new();
+    universe.set_topology("T6,6").unwrap();
+    universe.reset_all_dead();
+    universe.insert_glider(0, 0).unwrap();
+    let mut expected = Universe::new();
+    expected.set_topology("T6,6").unwrap();
+    expected.reset_all_dead();
+    expected.set_cells(&[(5, 0), (0, 1), (1, 5), (1, 0), (1, 1)]).unwrap();
+    assert_eq!(&universe.get_cells(), &expected.get_cells());
+
+    // On a plane, a glider that does not fit is not inserted at all.
+    universe.set_topology("P6,6").unwrap();
+    universe.reset_all_dead();
+    assert_eq!(
+        universe.insert_glider(0, 0),
+        Err(Error::OutOfBounds { row: -1, column: -1, width: 6, height: 6 })
+    );
+    expected.reset_all_dead();
+    assert_eq!(&universe.get_cells(), &expected.get_cells());
+    universe.insert_glider(1, 1).unwrap();
+    expected.set_cells(&[(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)]).unwrap();
+    assert_eq!(&universe.get_cells(), &expected.get_cells());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_sparse_universe() {
+    let mut universe = SparseUniverse::new();
+    assert_eq!(universe.chunk_count(), 0);
+    assert_eq!(universe.bounding_box(), None);
+
+    universe.toggle_cell(-1, -1);
+    universe.toggle_cell(1 << 40, 3);
+    assert!(universe.is_alive(-1, -1));
+    assert_eq!(universe.chunk_count(), 2);
+    assert_eq!(universe.bounding_box(), Some((-1, -1, 1 << 40, 3)));
+    universe.toggle_cell(1 << 40, 3);
+    assert_eq!(universe.chunk_count(), 1);
+
+    // Lone cells die, and their chunks are freed.
+    universe.tick();
+    assert_eq!(universe.population(), 0);
+    assert_eq!(universe.chunk_count(), 0);
+
+    // A glider travels across chunks without wrapping, leaving no chunks
+    // behind.
+    universe.insert_glider(0, 0);
+    for _ in 0..4 * 200 {
+        universe.tick();
+    }
+    assert_eq!(universe.population(), 5);
+    assert_eq!(universe.bounding_box(), Some((199, 199, 201, 201)));
+    assert!(universe.chunk_count() <= 4);
+    assert_eq!(universe.live_cells(), vec![(199, 200), (200, 201), (201, 199), (201, 200), (201, 201)]);
+
+    assert!(universe.set_rule("B36/S23").is_ok());
+    assert!(universe.set_rule("B2/S34H").is_ok());
+    assert!(universe.set_rule("B0/S8").is_err());
+    assert!(universe.set_rule("B2/S/C3").is_err());
+    assert!(universe.set_rule("R2,C0,M0,S5..9,B6..8,NM").is_err());
+    assert_eq!(universe.rule(), "B2/S34H");
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_tick_sparse() {
+    // A soup straddling the chunks around the origin evolves as on a plane
+    // large enough for it never to reach the edges.
+    for rule in ["B3/S23", "B36/S23", "B2-a/S12", "B2/S34H"].iter() {
+        let mut dense = Universe::new();
+        dense.set_topology("P64,64").unwrap();
+        dense.reset_all_dead();
+        dense.replace_rule(rule.parse().unwrap()).unwrap();
+        let soup = pseudo_random_universe(16, 16, 7);
+        let cells: Vec<(u32, u32)> = (0..16 * 16)
+            .filter(|&i| soup.get_cells()[i / 32] & 1 << (i % 32) != 0)
+            .map(|i| (24 + i as u32 / 16, 24 + i as u32 % 16))
+            .collect();
+        dense.set_cells(&cells).unwrap();
+
+        let mut sparse = SparseUniverse::new();
+        sparse.set_rule(rule).unwrap();
+        let offset = |(row, col): (u32, u32)| (row as i64 - 32, col as i64 - 32);
+        sparse.set_cells(&cells.iter().cloned().map(offset).collect::<Vec<_>>());
+
+        for generation in 0..16 {
+            let live: Vec<(i64, i64)> = (0..64 * 64)
+                .filter(|&i| dense.get_cells()[i / 32] & 1 << (i % 32) != 0)
+                .map(|i| offset((i as u32 / 64, i as u32 % 64)))
+                .collect();
+            assert_eq!(sparse.live_cells(), live, "{} at generation {}", rule, generation);
+            dense.tick().unwrap();
+            sparse.tick();
+        }
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_hashlife() {
+    let mut universe = Hashlife::new();
+    assert_eq!(universe.population(), 0);
+    assert_eq!(universe.bounding_box(), None);
+
+    // A glider moves one cell diagonally every 4 generations, however far
+    // it is run.
+    universe.insert_glider(-1, -1).unwrap();
+    assert_eq!(universe.bounding_box(), Some((-2, -2, 0, 0)));
+
+    // Cells more than 2^62 cells away from the origin are out of reach,
+    // and nothing is set when one of them is.
+    let reach = 1 << 62;
+    let error = Error::OutOfBounds { row: reach, column: 0, width: 1 << 63, height: 1 << 63 };
+    assert_eq!(universe.toggle_cell(reach, 0), Err(error.clone()));
+    assert_eq!(universe.set_cells(&[(0, 0), (reach, 0)]), Err(error.clone()));
+    assert_eq!(universe.insert_pulsar(reach - 7, 7), Err(error));
+    assert_eq!(universe.population(), 5);
+    universe.step_by(1 << 40).unwrap();
+    assert_eq!(universe.generation(), 1 << 40);
+    assert_eq!(universe.population(), 5);
+    let offset = 1 << 38;
+    assert_eq!(universe.bounding_box(), Some((offset - 2, offset - 2, offset, offset)));
+    assert!(universe.is_alive(offset - 2, offset - 1));
+    assert!(!universe.is_alive(offset - 2, offset - 2));
+
+    assert!(universe.step_by(1 << (MAX_STEP_LOG2 + 1)).is_err());
+    assert!(universe.set_rule("B0/S8").is_err());
+    assert!(universe.set_rule("B2/S/C3").is_err());
+
+    // Any number of generations is a sum of powers of two.
+    let mut sparse = SparseUniverse::new();
+    for rule in ["B3/S23", "B36/S23", "B2-a/S12", "B2/S34H"].iter() {
+        let soup = pseudo_random_universe(16, 16, 11);
+        let cells: Vec<(i64, i64)> = (0..16 * 16)
+            .filter(|&i| soup.get_cells()[i / 32] & 1 << (i % 32) != 0)
+            .map(|i| (i as i64 / 16 - 8, i as i64 % 16 - 8))
+            .collect();
+        universe.reset_all_dead();
+        universe.set_rule(rule).unwrap();
+        universe.set_cells(&cells).unwrap();
+        sparse.reset_all_dead();
+        sparse.set_rule(rule).unwrap();
+        sparse.set_cells(&cells);
+
+        universe.step_by(100).unwrap();
+        for _ in 0..100 {
+            sparse.tick();
+        }
+        assert_eq!(universe.population(), sparse.population(), "{}", rule);
+        assert_eq!(universe.bounding_box(), sparse.bounding_box(), "{}", rule);
+        let (top, left, bottom, right) = sparse.bounding_box().unwrap();
+        for (row, col) in sparse.live_cells() {
+            assert!(universe.is_alive(row, col), "{}", rule);
+        }
+        let dense = universe.to_universe(top, left, (right - left + 1) as u32, (bottom - top + 1) as u32);
+        assert_eq!(dense.get_cells().iter().map(|b| b.count_ones() as u64).sum::<u64>(), sparse.population());
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_hashlife_universe() {
+    // A pattern away from the edges of a dense universe evolves the same.
+    let mut dense = Universe::new();
+    dense.set_topology("P64,64").unwrap();
+    dense.reset_all_dead();
+    dense.insert_pulsar(20, 20).unwrap();
+    dense.insert_glider(40, 10).unwrap();
+    let mut universe = Hashlife::from_universe(&dense).unwrap();
+    assert_eq!(universe.population(), 48 + 5);
+
+    universe.step_by(16).unwrap();
+    for _ in 0..16 {
+        dense.tick().unwrap();
+    }
+    assert_eq!(universe.to_universe(0, 0, 64, 64).get_cells(), dense.get_cells());
+
+    // Collecting garbage keeps the universe as it is.
+    let nodes = universe.node_count();
+    universe.garbage_collect();
+    assert!(universe.node_count() < nodes);
+    universe.set_node_limit(0);
+    universe.step_by(8).unwrap();
+    for _ in 0..8 {
+        dense.tick().unwrap();
+    }
+    assert_eq!(universe.to_universe(0, 0, 64, 64).get_cells(), dense.get_cells());
+
+    dense.set_rule("B0/S8").unwrap();
+    assert!(Hashlife::from_universe(&dense).is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_tick_bitwise() {
+    // Computing 64 cells at a time gives the same generations as computing
+    // cell by cell, whether rows line up with the words of the bitset or
+    // not.
+    for &(width, height) in [(1, 1), (3, 5), (31, 7), (64, 64), (65, 9), (130, 12)].iter() {
+        for rule in ["B3/S23", "B36/S23", "B1357/S1357", "B2/S34H", "B/S012345678"].iter() {
+            for spec in ["T", "P", "K", "C"].iter() {
+                let spec = match *spec {
+                    "K" => format!("K{}*,{}", width, height),
+                    _ => format!("{}{},{}", spec, width, height),
+                };
+                let cells = pseudo_random_cells(width, height, width * height);
+                let mut universe = Universe::new();
+                universe.set_topology(&spec).unwrap();
+                universe.reset_all_dead();
+                universe.set_cells(&cells).unwrap();
+                universe.replace_rule(rule.parse().unwrap()).unwrap();
+                let mut reference = Universe::new();
+                reference.set_topology(&spec).unwrap();
+                reference.reset_all_dead();
+                reference.set_cells(&cells).unwrap();
+                reference.replace_rule(rule.parse().unwrap()).unwrap();
+
+                for generation in 0..4 {
+                    universe.tick().unwrap();
+                    reference.tick_scalar();
+                    assert_eq!(universe.get_cells(), reference.get_cells(), "{} on {} at {}", rule, spec, generation);
+                }
+            }
+        }
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_tick_lookup() {
+    // Looking up 2×2 tiles gives the same generations as computing cell by
+    // cell, including odd sizes that leave half a tile over, isotropic
+    // rules and rules with B0.
+    for &(width, height) in [(1, 1), (3, 5), (31, 7), (64, 64), (65, 9)].iter() {
+        for rule in ["B3/S23", "B2-a/S12", "B2/S34H", "B0/S8", "B013/S0123"].iter() {
+            for spec in ["T", "P", "C"].iter() {
+                let spec = format!("{}{},{}", spec, width, height);
+                let cells = pseudo_random_cells(width, height, width + 2 * height);
+                let mut universe = Universe::new();
+                universe.set_topology(&spec).unwrap();
+                universe.reset_all_dead();
+                universe.set_cells(&cells).unwrap();
+                universe.replace_rule(rule.parse().unwrap()).unwrap();
+                let mut reference = Universe::new();
+                reference.set_topology(&spec).unwrap();
+                reference.reset_all_dead();
+                reference.set_cells(&cells).unwrap();
+                reference.replace_rule(rule.parse().unwrap()).unwrap();
+
+                for generation in 0..4 {
+                    universe.tick_lookup();
+                    reference.tick_scalar();
+                    assert_eq!(universe.background(), reference.background(), "{} on {} at {}", rule, spec, generation);
+                    assert_eq!(universe.get_cells(), reference.get_cells(), "{} on {} at {}", rule, spec, generation);
+                }
+            }
+        }
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_tick_active() {
+    // Recomputing only the tiles around changes gives the same generations
+    // as computing every cell, also across the edges, after cells are set
+    // between generations, and while a B0 background flips.
+    for &(width, height) in [(5, 3), (64, 64), (70, 33), (100, 97)].iter() {
+        for rule in ["B3/S23", "B2-a/S12", "B2/S34H", "B0/S8", "B013/S0123", "B0124/S03", "B01/S3", "B012578/S024"].iter() {
+            for spec in ["T", "P", "C"].iter() {
+                let spec = format!("{}{},{}", spec, width, height);
+                let cells = pseudo_random_cells(width, height, width * height / 4);
+                let mut universe = Universe::new();
+                universe.set_topology(&spec).unwrap();
+                universe.reset_all_dead();
+                universe.set_cells(&cells).unwrap();
+                universe.replace_rule(rule.parse().unwrap()).unwrap();
+                let mut reference = Universe::new();
+                reference.set_topology(&spec).unwrap();
+                reference.reset_all_dead();
+                reference.set_cells(&cells).unwrap();
+                reference.replace_rule(rule.parse().unwrap()).unwrap();
+
+                for generation in 0..12 {
+                    if generation == 6 {
+                        // Across the right edge, unless nothing is there.
+                        let column = if spec.starts_with('P') { width - 2 } else { width - 1 };
+                        universe.insert_glider(height / 2, column).unwrap();
+                        reference.insert_glider(height / 2, column).unwrap();
+                    }
+                    let before = reference.get_cells().to_vec();
+                    let background = reference.background();
+                    universe.tick_active();
+                    reference.tick_scalar();
+                    assert_eq!(universe.get_cells(), reference.get_cells(), "{} on {} at {}", rule, spec, generation);
+
+                    let changed = (0..(width * height) as usize)
+                        .filter(|&i| {
+                            let bit = |cells: &[u32]| cells[i / 32] >> (i % 32) & 1 != 0;
+                            (bit(&before) != background) != (bit(reference.get_cells()) != reference.background())
+                        })
+                        .count() as u32;
+                    assert_eq!(universe.changed_cells(), Some(changed), "{} on {} at {}", rule, spec, generation);
+                    assert_eq!(universe.population(), reference.population(), "{} on {} at {}", rule, spec, generation);
+                }
+            }
+        }
+    }
+
+    // The background of B01/S3 flips every generation, through `tick` too.
+    let cells = pseudo_random_cells(5, 3, 4);
+    let build = |algorithm: &str| {
+        let mut universe = UniverseBuilder::new().boundary("T5+3,3").rule("B01/S3").build().unwrap();
+        universe.set_cells(&cells).unwrap();
+        universe.set_algorithm(algorithm).unwrap();
+        universe
+    };
+    let (mut universe, mut reference) = (build("active"), build("scalar"));
+    for generation in 0..24 {
+        universe.tick().unwrap();
+        reference.tick_scalar();
+        assert_eq!(universe.get_cells(), reference.get_cells(), "at {}", generation);
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_active_tiles() {
+    // Once a pattern settles, only the tiles around what still moves are
+    // recomputed.
+    let mut universe = Universe::new();
+    universe.set_topology("T128,128").unwrap();
+    universe.reset_all_dead();
+    universe.set_cells(&[(10, 10), (10, 11), (11, 10), (11, 11)]).unwrap();
+    universe.set_algorithm("active").unwrap();
+    assert_eq!(universe.active_tiles(), None);
+    assert_eq!(universe.population(), 4);
+
+    universe.tick().unwrap();
+    assert_eq!(universe.active_tiles(), Some(16));
+    assert_eq!(universe.changed_cells(), Some(0));
+    universe.tick().unwrap();
+    assert_eq!(universe.active_tiles(), Some(0));
+    assert_eq!(universe.population(), 4);
+
+    // A glider in the middle of a tile only wakes that tile and the ones
+    // around it.
+    universe.insert_glider(48, 48).unwrap();
+    assert_eq!(universe.population(), 9);
+    universe.tick().unwrap();
+    assert_eq!(universe.active_tiles(), Some(9));
+    assert_eq!(universe.changed_cells(), Some(4));
+    universe.tick().unwrap();
+    assert_eq!(universe.active_tiles(), Some(9));
+    assert_eq!(universe.population(), 9);
+
+    // Ticking another way stops the tracking.
+    universe.tick_scalar();
+    assert_eq!(universe.active_tiles(), None);
+    assert_eq!(universe.population(), 9);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_algorithm() {
+    assert_eq!("lookup".parse(), Ok(Algorithm::LookupTable));
+    assert_eq!(" Bitwise ".parse(), Ok(Algorithm::Bitwise));
+    assert!("fastest".parse::<Algorithm>().is_err());
+
+    let mut universe = Universe::new();
+    assert_eq!(universe.algorithm(), "auto");
+    assert!(universe.set_algorithm("fastest").is_err());
+
+    // Every algorithm, including one that does not support the rule and
+    // falls back, gives the same generations.
+    for rule in ["B3/S23", "B2-a/S12", "B2/S/C3"].iter() {
+        let mut reference = Universe::new();
+        reference.reset_all_dead();
+        reference.set_cells(&pseudo_random_cells(64, 64, 5)).unwrap();
+        reference.set_rule(rule).unwrap();
+        for _ in 0..4 {
+            reference.tick_scalar();
+        }
+
+        for algorithm in ["auto", "scalar", "bitwise", "lookup", "active"].iter() {
+            universe.reset_all_dead();
+            universe.set_cells(&pseudo_random_cells(64, 64, 5)).unwrap();
+            universe.set_rule(rule).unwrap();
+            universe.set_algorithm(algorithm).unwrap();
+            assert_eq!(universe.algorithm(), *algorithm);
+            for _ in 0..4 {
+                universe.tick().unwrap();
+            }
+            assert_eq!(universe.get_cells(), reference.get_cells(), "{} with {}", rule, algorithm);
+            assert_eq!(universe.get_cell_states(), reference.get_cell_states(), "{} with {}", rule, algorithm);
+        }
+    }
+}
+
+#[cfg(feature = "parallel")]
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_tick_in_bands() {
+    // However the rows are split into bands, including bands wrapping
+    // around the edges, the generation is the same as computed serially.
+    for &(width, height) in [(33, 70), (64, 64), (100, 37), (130, 130)].iter() {
+        for spec in ["T", "P", "C"].iter() {
+            let spec = format!("{}{},{}", spec, width, height);
+            let cells = pseudo_random_cells(width, height, width + height);
+            for bands in 1..=8 {
+                let mut universe = Universe::new();
+                universe.set_topology(&spec).unwrap();
+                universe.reset_all_dead();
+                let mut expected = Universe::new();
+                expected.set_topology(&spec).unwrap();
+                expected.reset_all_dead();
+                universe.set_cells(&cells).unwrap();
+                expected.set_cells(&cells).unwrap();
+
+                for generation in 0..4 {
+                    universe.tick_in_bands(bands);
+                    expected.tick_scalar();
+                    assert_eq!(universe.get_cells(), expected.get_cells(), "{} bands on {} at {}", bands, spec, generation);
+                }
+            }
+        }
+    }
+
+    // Rules that cannot be split into bands are computed serially.
+    for rule in ["B2/S/C3", "B2-a/S12", "B0/S8", "R2,C0,M0,S5..9,B6..8,NM"].iter() {
+        let mut universe = pseudo_random_universe(40, 30, 7);
+        let mut expected = pseudo_random_universe(40, 30, 7);
+        universe.set_rule(rule).unwrap();
+        expected.set_rule(rule).unwrap();
+        for generation in 0..4 {
+            universe.tick_in_bands(4);
+            expected.tick_scalar();
+            assert_eq!(universe.get_cells(), expected.get_cells(), "{} at {}", rule, generation);
+            assert_eq!(universe.get_cell_states(), expected.get_cell_states(), "{} at {}", rule, generation);
+        }
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_engines() {
+    // Every engine behind the trait follows the same R-pentomino, once it
+    // has left the rows and columns it started in.
+    let mut universe = Universe::new();
+    universe.set_topology("P64,64").unwrap();
+    universe.reset_all_dead();
+    let mut engines: Vec<Box<dyn Engine>> =
+        vec![Box::new(universe), Box::new(SparseUniverse::new()), Box::new(Hashlife::new())];
+    let mut expected = None;
+    for engine in engines.iter_mut() {
+        for &(row, column) in [(30, 31), (30, 32), (31, 30), (31, 31), (32, 31)].iter() {
+            engine.set(row, column, true).unwrap();
+        }
+        assert!(engine.get(31, 30));
+        assert_eq!(engine.population(), 5);
+        assert_eq!(engine.bounding_box(), Some((30, 30, 32, 32)));
+
+        engine.step(20).unwrap();
+        let cells = engine.live_cells();
+        assert_eq!(cells.len() as u64, engine.population(), "{}", engine.name());
+        assert!(cells.windows(2).all(|pair| pair[0] < pair[1]), "{}", engine.name());
+        match &expected {
+            Some(expected) => assert_eq!(&cells, expected, "{}", engine.name()),
+            None => expected = Some(cells),
+        }
+
+        engine.clear();
+        assert_eq!(engine.population(), 0);
+        assert_eq!(engine.bounding_box(), None);
+    }
+    assert_eq!(engines[0].name(), "dense");
+    assert!(engines[1].replace_rule("B2/S/C3".parse().unwrap()).is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_backend() {
+    assert_eq!("Hashlife".parse(), Ok(Backend::Hashlife));
+    assert!("quadtree".parse::<Backend>().is_err());
+
+    // The cells are carried over from one backend to the next, which
+    // computes the same generations as long as nothing reaches the edges.
+    let mut universe = Universe::new();
+    universe.reset_all_dead();
+    universe.insert_pulsar(32, 32).unwrap();
+    universe.insert_glider(10, 10).unwrap();
+    let mut reference = Universe::new();
+    reference.reset_all_dead();
+    reference.insert_pulsar(32, 32).unwrap();
+    reference.insert_glider(10, 10).unwrap();
+
+    assert!(universe.set_backend("quadtree").is_err());
+    for backend in ["sparse", "hashlife", "dense", "hashlife", "sparse"].iter() {
+        universe.set_backend(backend).unwrap();
+        assert_eq!(universe.backend(), *backend);
+        assert_eq!(universe.get_cells(), reference.get_cells(), "{}", backend);
+        universe.tick().unwrap();
+        universe.step(2).unwrap();
+        reference.step(3).unwrap();
+        assert_eq!(universe.get_cells(), reference.get_cells(), "{}", backend);
+        assert_eq!(universe.population(), reference.population(), "{}", backend);
+    }
+
+    // Unbounded engines keep the cells that leave the view, and reject the
+    // rules they cannot compute.
+    universe.set_topology("T8,8").unwrap();
+    universe.reset_all_dead();
+    universe.set_cells(&[(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)]).unwrap();
+    universe.step(4).unwrap();
+    assert!(Grid::get(&universe, 3, 3));
+    universe.set_rule("B0/S8").unwrap_err();
+    universe.set_rule("B36/S23").unwrap();
+    assert_eq!(universe.rule(), "B36/S23");
+    universe.step(40).unwrap();
+    assert_eq!(universe.population(), 5);
+    assert!(universe.get_cells().iter().all(|&block| block == 0));
+    assert_eq!(universe.engine().unwrap().bounding_box(), Some((11, 11, 13, 13)));
+
+    // The algorithms of the universe itself leave the cells of the engine
+    // alone.
+    universe.set_cells(&[(1, 1), (1, 2), (2, 1)]).unwrap();
+    universe.tick_scalar();
+    universe.tick_lookup();
+    universe.tick_active();
+    assert_eq!(universe.population(), 8);
+    assert_eq!(universe.active_tiles(), None);
+    universe.tick().unwrap();
+    assert_eq!(universe.population(), 9);
+
+    // Back in the universe itself, only the cells in view remain.
+    universe.set_backend("dense").unwrap();
+    assert!(universe.engine().is_none());
+    assert_eq!(universe.population(), 4);
+
+    let universe = Universe::with_engine(16, 16, Box::new(SparseUniverse::new()));
+    assert_eq!(universe.backend(), "sparse");
+    assert_eq!(universe.get_cells().len(), 8);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_lockstep() {
+    // The dense algorithms agree with each other on a soup.
+    let universe = |algorithm: Algorithm| {
+        let mut universe = Universe::new();
+        universe.set_topology("T48,40").unwrap();
+        universe.reset_all_dead();
+        universe.set_cells(&pseudo_random_cells(48, 40, 11)).unwrap();
+        universe.replace_algorithm(algorithm);
+        Box::new(universe)
+    };
+    for &algorithm in [Algorithm::Bitwise, Algorithm::LookupTable, Algorithm::ActiveTiles].iter() {
+        let mut lockstep = Lockstep::new(universe(Algorithm::Scalar), universe(algorithm)).unwrap();
+        assert_eq!(lockstep.run(50), Ok(None), "{}", algorithm);
+        assert_eq!(lockstep.generation(), 50);
+        assert_eq!(lockstep.reference().live_cells(), lockstep.candidate().live_cells());
+    }
+
+    // HighLife gives birth to cells with 6 neighbours where Life does not.
+    let mut life = SparseUniverse::new();
+    let mut highlife = SparseUniverse::new();
+    highlife.set_rule("B36/S23").unwrap();
+    let cells = [(0, 0), (0, 1), (0, 2), (2, 0), (2, 1), (2, 2), (5, 5)];
+    life.set_cells(&cells);
+    highlife.set_cells(&cells);
+
+    let mut lockstep = Lockstep::new(Box::new(life), Box::new(highlife)).unwrap();
+    let divergence = lockstep.run(10).unwrap().unwrap();
+    assert_eq!((divergence.generation, divergence.row, divergence.column), (1, 1, 1));
+    assert!(!divergence.expected);
+    assert_eq!(divergence.neighborhood[1][1..4], [true; 3]);
+    assert_eq!(divergence.neighborhood[2][1..4], [false; 3]);
+    assert_eq!(divergence.neighborhood[3][1..4], [true; 3]);
+    assert_eq!(lockstep.generation(), 0);
+    assert!(divergence.to_string().starts_with("generation 1, row 1, column 1: expected dead, found alive\n"));
+
+    // Engines that hold different cells disagree from the start.
+    let mut life = SparseUniverse::new();
+    let mut other = SparseUniverse::new();
+    life.set_cells(&cells);
+    other.set_cells(&cells);
+    other.toggle_cell(0, 3);
+    let divergence = Lockstep::new(Box::new(life), Box::new(other)).err().unwrap();
+    assert_eq!((divergence.generation, divergence.row, divergence.column), (0, 0, 3));
+    assert!(!divergence.expected);
+    assert_eq!(divergence.neighborhood[2][..3], [true, true, false]);
+    assert!(divergence.to_string().contains("the cells around it in the reference engine:\n"));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_random() {
+    // The reference outputs of SplitMix64 from the seed 0, which every
+    // platform must give.
+    let mut random = Random::new(0);
+    assert_eq!(random.next_u64(), 0xe220_a839_7b1d_cdaf);
+    assert_eq!(random.next_u64(), 0x6e78_9e6a_a1b9_65f4);
+    assert_eq!(random.next_u64(), 0x06c4_5d18_8009_454f);
+
+    let mut random = Random::new(42);
+    assert!((0..1000).map(|_| random.next_f64()).all(|x| (0.0..1.0).contains(&x)));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_soup() {
+    assert_eq!("d8".parse(), Ok(Symmetry::D8));
+    assert!("C3".parse::<Symmetry>().is_err());
+
+    let soup = Soup { seed: 7, density: 0.5, region: Some((2, 3, 16, 16)), symmetry: Symmetry::C1 };
+    let cells = soup.cells(32, 24).unwrap();
+    assert_eq!(cells, soup.cells(32, 24).unwrap());
+    assert!(cells.iter().all(|&(row, column)| (2..18).contains(&row) && (3..19).contains(&column)));
+    assert!((64..192).contains(&cells.len()));
+    assert_ne!(cells, Soup { seed: 8, ..soup.clone() }.cells(32, 24).unwrap());
+
+    assert_eq!(Soup { density: 0.0, ..soup.clone() }.cells(32, 24).unwrap(), []);
+    assert_eq!(Soup { density: 1.0, ..soup.clone() }.cells(32, 24).unwrap().len(), 256);
+    assert!(Soup { density: 1.5, ..soup.clone() }.cells(32, 24).is_err());
+    assert!(soup.cells(16, 24).is_err());
+    assert!(Soup { symmetry: Symmetry::C4, region: Some((0, 0, 8, 6)), ..soup.clone() }.cells(32, 24).is_err());
+
+    // Every symmetric image of a live cell in the rectangle is alive.
+    let images = |symmetry, r: u32, c: u32| match symmetry {
+        Symmetry::C2 => vec![(15 - r, 15 - c)],
+        Symmetry::C4 => vec![(c, 15 - r)],
+        Symmetry::D2 => vec![(r, 15 - c)],
+        Symmetry::D4 => vec![(r, 15 - c), (15 - r, c)],
+        _ => vec![(c, 15 - r), (r, 15 - c), (c, r)],
+    };
+    for &symmetry in [Symmetry::C2, Symmetry::C4, Symmetry::D2, Symmetry::D4, Symmetry::D8].iter() {
+        let cells = Soup { symmetry, ..soup.clone() }.cells(32, 24).unwrap();
+        assert!(!cells.is_empty());
+        for &(row, column) in &cells {
+            for (r, c) in images(symmetry, row - 2, column - 3) {
+                assert!(cells.contains(&(r + 2, c + 3)), "{} image of ({}, {})", symmetry, row, column);
+            }
+        }
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_reset_with() {
+    let mut universe = Universe::new();
+    assert!(universe.seed().is_some());
+
+    let seed = universe.reset_with(None, 0.3, None, None).unwrap();
+    assert_eq!(universe.seed(), Some(seed));
+    let cells = universe.get_cells().to_vec();
+    universe.reset_with(Some(seed + 1), 0.3, None, None).unwrap();
+    assert_ne!(universe.get_cells(), &cells[..]);
+    assert_eq!(universe.reset_with(Some(seed), 0.3, None, None), Ok(seed));
+    assert_eq!(universe.get_cells(), &cells[..]);
+
+    universe.reset_with(Some(1), 1.0, Some(vec![10, 20, 4, 5]), Some("D4".to_string())).unwrap();
+    assert_eq!(universe.population(), 20);
+    assert_eq!(universe.bounding_box(), Some((10, 20, 13, 24)));
+
+    // A soup that cannot be made leaves the universe untouched.
+    assert!(universe.reset_with(Some(2), 0.5, Some(vec![10, 20, 4]), None).is_err());
+    assert!(universe.reset_with(Some(2), 0.5, Some(vec![60, 60, 8, 8]), None).is_err());
+    assert!(universe.reset_with(Some(2), 0.5, None, Some("C8".to_string())).is_err());
+    assert_eq!(universe.population(), 20);
+    assert_eq!(universe.seed(), Some(1));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_universe_builder() {
+    let universe = UniverseBuilder::new().build().unwrap();
+    assert_eq!((universe.width(), universe.height()), (64, 64));
+    assert_eq!(universe.rule(), "B3/S23");
+    assert_eq!(universe.topology(), "T64,64");
+    assert_eq!(universe.population(), 0);
+
+    let universe = UniverseBuilder::new()
+        .size(100, 80)
+        .rule("B36/S23")
+        .boundary("P100,80")
+        .soup(Some(9), 0.25)
+        .build()
+        .unwrap();
+    assert_eq!(universe.topology(), "P100,80");
+    assert_eq!(universe.rule(), "B36/S23");
+    assert_eq!(universe.seed(), Some(9));
+    let mut replay = Universe::new();
+    replay.set_topology("P100,80").unwrap();
+    replay.reset_with(Some(9), 0.25, None, None).unwrap();
+    assert_eq!(universe.get_cells(), replay.get_cells());
+
+    // The size of the grid is the size of the universe.
+    let universe = UniverseBuilder::new().boundary("K40*,30").build().unwrap();
+    assert_eq!((universe.width(), universe.height()), (40, 30));
+
+    let glider = UniverseBuilder::new().size(9, 7).pattern("x = 3, y = 3\nbo$2bo$3o!").build().unwrap();
+    assert_eq!(glider.live_cells(), [(2, 4), (3, 5), (4, 3), (4, 4), (4, 5)]);
+    let plain = UniverseBuilder::new().size(9, 7).pattern("!Name: Glider\n.O\n..O\nOOO").build().unwrap();
+    assert_eq!(plain.get_cells(), glider.get_cells());
+    let headerless = UniverseBuilder::new().size(9, 7).pattern("bo$2bo$3o!").build().unwrap();
+    assert_eq!(headerless.get_cells(), glider.get_cells());
+    let rle = UniverseBuilder::new().pattern("#N Blinker\nx = 3, y = 1, rule = B36/S23\n3o!").build().unwrap();
+    assert_eq!(rle.rule(), "B36/S23");
+    assert_eq!(rle.live_cells(), [(31, 30), (31, 31), (31, 32)]);
+    let rule = UniverseBuilder::new().rule("B3/S23").pattern("x = 3, y = 1, rule = B36/S23\n3o!").build().unwrap();
+    assert_eq!(rule.rule(), "B3/S23");
+
+    let errors = [
+        UniverseBuilder::new().size(0, 10),
+        UniverseBuilder::new().size(1 << 16, 1 << 16),
+        UniverseBuilder::new().rule("B3/S2x"),
+        UniverseBuilder::new().boundary("Q64,64"),
+        UniverseBuilder::new().size(32, 32).boundary("T64,64"),
+        UniverseBuilder::new().soup(None, 2.0),
+        UniverseBuilder::new().soup(Some(1), 0.5).pattern("3o!"),
+        UniverseBuilder::new().pattern("3o$2x!"),
+        UniverseBuilder::new().pattern(".O\n.X"),
+        UniverseBuilder::new().pattern("x = 3, z = 1\n3o!"),
+        UniverseBuilder::new().size(2, 2).pattern("3o!"),
+        UniverseBuilder::new().size(2, 2).pattern("x = 3, y = 1\n3o!"),
+        UniverseBuilder::new().pattern("4294967295o!"),
+        UniverseBuilder::new().pattern("o4294967295$o!"),
+    ];
+    for builder in errors.iter() {
+        assert!(builder.build().is_err(), "{:?}", builder);
+    }
+    assert_eq!(
+        UniverseBuilder::new().size(32, 32).boundary("T64,64").build().err(),
+        Some(Error::InvalidSize("grid 'T64,64' is 64×64, but the universe is 32×32".to_string()))
+    );
+    // The settings of a plain JavaScript object, where a typo is refused
+    // instead of leaving a setting out.
+    let settings = vec![
+        ("width", ConfigValue::Number(20.0)),
+        ("height", ConfigValue::Number(10.0)),
+        ("rule", ConfigValue::String("B36/S23".to_string())),
+        ("seed", ConfigValue::BigInt(7)),
+    ];
+    let universe = UniverseBuilder::from_settings(settings).unwrap().build().unwrap();
+    let expected = UniverseBuilder::new().size(20, 10).rule("B36/S23").soup(Some(7), 0.5).build().unwrap();
+    assert_eq!(universe.get_cells(), expected.get_cells());
+    assert_eq!(universe.rule(), expected.rule());
+    let errors = [
+        vec![("widht", ConfigValue::Number(128.0))],
+        vec![("desnity", ConfigValue::Number(0.3))],
+        vec![("width", ConfigValue::Number(128.0))],
+        vec![("width", ConfigValue::Number(1.5)), ("height", ConfigValue::Number(2.0))],
+        vec![("rule", ConfigValue::Number(3.0))],
+        vec![("seed", ConfigValue::Number(-1.0))],
+        vec![("pattern", ConfigValue::Other)],
+    ];
+    for settings in errors.iter() {
+        let error = UniverseBuilder::from_settings(settings.iter().cloned()).unwrap_err();
+        assert_eq!(error.code(), "InvalidArgument", "{:?}", settings);
+    }
+    assert_eq!(
+        UniverseBuilder::from_settings(vec![("widht", ConfigValue::Number(128.0))]).unwrap_err().to_string(),
+        "unknown setting 'widht', expected width, height, rule, boundary, seed, density or pattern"
+    );
+
+    // A long run fails as soon as it leaves the universe.
+    assert_eq!(
+        UniverseBuilder::new().size(9, 7).pattern("bo$999999999o!").build().err(),
+        Some(Error::InvalidArgument("a 999999999×2 pattern does not fit in a 9×7 universe".to_string()))
+    );
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_resize() {
+    assert_eq!(" Bottom-Right ".parse(), Ok(Anchor::BottomRight));
+    assert_eq!("centre".parse(), Ok(Anchor::Center));
+    assert!("middle".parse::<Anchor>().is_err());
+    assert_eq!(Anchor::Center.offset(8, 6, 11, 9), (1, 1));
+    assert_eq!(Anchor::Center.offset(11, 9, 8, 6), (-1, -1));
+    assert_eq!(Anchor::BottomRight.offset(8, 6, 11, 9), (3, 3));
+
+    let glider: [(u32, u32); 5] = [(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)];
+    let moved = |rows: i64, columns: i64| -> Vec<(i64, i64)> {
+        glider.iter().map(|&(row, column)| (row as i64 + rows, column as i64 + columns)).collect()
+    };
+    let mut universe = UniverseBuilder::new().size(8, 6).build().unwrap();
+    universe.set_cells(&glider.iter().map(|&(row, column)| (row + 2, column + 3)).collect::<Vec<_>>()).unwrap();
+
+    universe.resize(12, 10, "top-left").unwrap();
+    assert_eq!((universe.width(), universe.height()), (12, 10));
+    assert_eq!(universe.topology(), "T12,10");
+    assert_eq!(universe.live_cells(), moved(2, 3));
+    universe.resize(16, 12, "center").unwrap();
+    assert_eq!(universe.live_cells(), moved(3, 5));
+    universe.resize_anchored(18, 12, Anchor::BottomRight).unwrap();
+    assert_eq!(universe.live_cells(), moved(3, 7));
+
+    // Cropping removes the cells that no longer fit.
+    universe.resize(9, 5, "right").unwrap();
+    assert_eq!(universe.live_cells(), [(1, 0), (2, 0)]);
+    assert!(universe.resize(0, 5, "top").is_err());
+    assert!(universe.resize(5, 5, "middle").is_err());
+    assert_eq!((universe.width(), universe.height()), (9, 5));
+
+    // The cells still compute the same generations after resizing.
+    universe.reset_all_dead();
+    universe.set_cells(&glider).unwrap();
+    universe.resize(100, 100, "top-left").unwrap();
+    universe.toggle_cell(99, 99).unwrap();
+    universe.toggle_cell(99, 99).unwrap();
+    universe.step(4).unwrap();
+    assert_eq!(universe.live_cells(), moved(1, 1));
+
+    universe.auto_fit(2).unwrap();
+    assert_eq!((universe.width(), universe.height()), (7, 7));
+    assert_eq!(universe.live_cells(), moved(2, 2));
+    universe.auto_fit(0).unwrap();
+    assert_eq!(universe.live_cells(), moved(0, 0));
+    universe.reset_all_dead();
+    assert!(universe.auto_fit(1).is_err());
+
+    // Growing past the first size keeps working, also one size at a time.
+    let mut universe = UniverseBuilder::new().build().unwrap();
+    universe.set_width(100).unwrap();
+    universe.set_height(90).unwrap();
+    universe.toggle_cell(89, 99).unwrap();
+    assert_eq!(universe.live_cells(), [(89, 99)]);
+    assert_eq!(universe.get_cells().len(), (100 * 90usize).div_ceil(32));
+    assert_eq!(universe.set_width(0).unwrap_err().code(), "InvalidSize");
+    assert_eq!(universe.set_height(u32::MAX / 100 + 1).unwrap_err().code(), "InvalidSize");
+    assert_eq!((universe.width(), universe.height()), (100, 90));
+
+    // Refractory states and an alive background are kept.
+    let mut universe = UniverseBuilder::new().size(10, 10).rule("B2/S/C3").build().unwrap();
+    universe.set_cells(&[(4, 4), (4, 5)]).unwrap();
+    universe.tick().unwrap();
+    let states = universe.get_cell_states().to_vec();
+    universe.resize(20, 20, "center").unwrap();
+    for row in 0..10 {
+        assert_eq!(universe.get_cell_states()[(row + 5) * 20 + 5..][..10], states[row * 10..][..10]);
+    }
+    let mut universe = UniverseBuilder::new().size(10, 10).rule("B0/S8").pattern("o!").build().unwrap();
+    universe.tick().unwrap();
+    assert!(universe.background());
+    let population = universe.population();
+    universe.resize(12, 12, "center").unwrap();
+    assert_eq!(universe.population(), population);
+    assert!(!Grid::get(&universe, 0, 0));
+    let mut universe = UniverseBuilder::new().size(8, 8).rule("B0/S8").build().unwrap();
+    universe.tick().unwrap();
+    assert_eq!(universe.population(), 64);
+    universe.resize(10, 10, "top-left").unwrap();
+    assert_eq!(universe.population(), 64);
+    assert_eq!(universe.live_cells(), (0..64).map(|i| (i / 8, i % 8)).collect::<Vec<_>>());
+
+    // The cells of another engine all move, also the ones out of view.
+    let mut universe = UniverseBuilder::new().size(8, 8).build().unwrap();
+    universe.set_backend("sparse").unwrap();
+    universe.set_cells(&[(0, 0), (7, 7)]).unwrap();
+    universe.resize(4, 4, "center").unwrap();
+    assert_eq!(universe.engine().unwrap().live_cells(), [(-2, -2), (5, 5)]);
+    assert_eq!(universe.population(), 2);
+    assert_eq!(universe.get_cells()[0], 0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_tick_allocations() {
+    // Once the back buffers are allocated, ticking swaps them instead of
+    // allocating new ones.
+    for rule in ["B3/S23", "B2/S34H", "B2-a/S12", "B2/S/C3", "B0/S8"].iter() {
+        let mut universe = Universe::new();
+        universe.reset_all_dead();
+        universe.set_cells(&pseudo_random_cells(64, 64, 3)).unwrap();
+        universe.set_rule(rule).unwrap();
+        universe.tick().unwrap();
+        universe.tick_scalar();
+
+        let before = allocations();
+        for _ in 0..8 {
+            universe.tick().unwrap();
+            universe.tick_scalar();
+        }
+        assert_eq!(allocations(), before, "{}", rule);
+
+        // Tracking the tiles that changed does not allocate either, once
+        // they are split up.
+        if rule != &"B2/S/C3" {
+            universe.tick_active();
+            let before = allocations();
+            for _ in 0..8 {
+                universe.tick_active();
+            }
+            assert_eq!(allocations(), before, "{}", rule);
+        }
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_errors() {
+    // Cells outside of the universe are reported, and nothing is changed.
+    let mut universe = UniverseBuilder::new().size(6, 4).build().unwrap();
+    universe.set_cells(&[(1, 1)]).unwrap();
+    let cells = universe.get_cells().to_vec();
+    let error = Error::OutOfBounds { row: 4, column: 2, width: 6, height: 4 };
+    assert_eq!(universe.toggle_cell(4, 2), Err(error.clone()));
+    assert_eq!(universe.set_cells(&[(2, 2), (4, 2)]), Err(error.clone()));
+    assert_eq!(universe.insert_pulsar(4, 2), Err(error.clone()));
+    assert_eq!(universe.get_cells(), &cells[..]);
+    assert_eq!(error.code(), "OutOfBounds");
+    assert_eq!(error.to_string(), "row 4, column 2 is outside of the 6×4 universe");
+    universe.set_topology("P6,4").unwrap();
+    let error = Error::OutOfBounds { row: -1, column: 2, width: 6, height: 4 };
+    assert_eq!(Grid::set(&mut universe, -1, 2, true), Err(error));
+    assert_eq!(universe.get_cells(), &cells[..]);
+
+    // Every kind of failure has its own code.
+    let code = |result: Result<(), Error>| result.unwrap_err().code();
+    assert_eq!(code("B9/S23".parse::<Rule>().map(drop)), "InvalidRule");
+    assert_eq!(code(Rule::from_counts(&[9], &[2, 3]).map(drop)), "InvalidRule");
+    assert_eq!(code(Rule::from_hex_counts(&[2], &[7]).map(drop)), "InvalidRule");
+    assert_eq!(code(Rule::from_triangular_counts(Neighborhood::Moore, &[3], &[2, 3]).map(drop)), "InvalidRule");
+    assert_eq!(code(Rule::from_triangular_counts(Neighborhood::TriangularEdges, &[0], &[1]).map(drop)), "InvalidRule");
+    assert_eq!(code(Rule::conway().with_states(1).map(drop)), "InvalidRule");
+    assert_eq!(code(Rule::from_counts(&[0], &[8]).unwrap().with_states(3).map(drop)), "InvalidRule");
+    assert_eq!(Rule::conway().with_states(3).unwrap().to_string(), "B3/S23/C3");
+    assert_eq!(Neighborhood::Custom.size(1), None);
+    assert_eq!(Neighborhood::Hexagonal.size(2), Some(18));
+    assert_eq!(code(universe.set_rule("B3/S23/X")), "InvalidRule");
+    assert_eq!(code(universe.set_topology("X6,4")), "ParseError");
+    assert_eq!(code(universe.set_topology("T70000,70000")), "InvalidSize");
+    assert_eq!(code(universe.set_backend("quantum")), "ParseError");
+    assert_eq!(code(universe.reset_with(Some(1), 2.0, None, None).map(drop)), "InvalidArgument");
+    assert_eq!(code(UniverseBuilder::new().pattern("x = 2, y = 1\n2q!").build().map(drop)), "ParseError");
+
+    // A valid rule that the engine cannot compute keeps the rule it had.
+    universe.set_backend("sparse").unwrap();
+    assert_eq!(code(universe.set_rule("B0/S8")), "UnsupportedRule");
+    assert_eq!(code(universe.replace_rule("B0/S8".parse().unwrap()).map(drop)), "UnsupportedRule");
+    assert_eq!(universe.rule(), "B3/S23");
+
+    // So does an engine that cannot advance, through `tick` too.
+    universe.set_backend("hashlife").unwrap();
+    Grid::set(&mut universe, (1 << 62) - 1, 0, true).unwrap();
+    assert_eq!(code(universe.tick()), "StepFailed");
+
+    // Cells out of the engine's reach are refused, including when the
+    // universe is resized to move them there.
+    let cells = Grid::live_cells(&universe);
+    assert_eq!(code(Grid::set(&mut universe, 1 << 62, 0, true)), "OutOfBounds");
+    assert_eq!(code(universe.resize(universe.width(), universe.height() + 2, "bottom")), "OutOfBounds");
+    assert_eq!(Grid::live_cells(&universe), cells);
+}
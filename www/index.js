@@ -1,21 +1,50 @@
-import { Universe, Cell } from "rust-wasm-game-of-life";
+import { UniverseBuilder, Cell } from "rust-wasm-game-of-life";
 import { memory } from "rust-wasm-game-of-life/rust_wasm_game_of_life_bg";
 
 const CELL_SIZE = 5; // px
+const HEX_SIZE = 4; // px, from the centre of a hexagon to a corner
+const TRIANGLE_SIZE = 10; // px, the side of a triangle
 const GRID_COLOR = "#CCCCCC";
 const DEAD_COLOR = "#FFFFFF";
 const ALIVE_COLOR = "#000000";
 
 // Construct the universe, and get its width and height.
-const universe = Universe.new();
+const universe = UniverseBuilder.from_config({ width: 64, height: 64, density: 0.5 }).build();
 const width = universe.width();
 const height = universe.height();
 
+// Hexagonal and triangular rules draw every cell as a hexagon or a
+// triangle instead of a square.
+const gridOf = () => {
+    if (universe.is_hexagonal()) {
+        return "hexagonal";
+    }
+    if (universe.is_triangular()) {
+        return "triangular";
+    }
+    return "square";
+};
+let grid = gridOf();
+
 // Give the canvas room for all of our cells and a 1px border
 // around each of them.
 const canvas = document.getElementById("game-of-life-canvas");
-canvas.height = (CELL_SIZE + 1) * height + 1;
-canvas.width = (CELL_SIZE + 1) * width + 1;
+
+const resizeCanvas = () => {
+    if (grid === "hexagonal") {
+        // Odd rows stick out half a hexagon to the left.
+        canvas.height = Math.ceil(HEX_SIZE * (1.5 * height + 0.5)) + 1;
+        canvas.width = Math.ceil(Math.sqrt(3) * HEX_SIZE * (width + 0.5)) + 1;
+    } else if (grid === "triangular") {
+        // Triangles overlap their neighbours by half a triangle.
+        canvas.height = Math.ceil(Math.sqrt(3) / 2 * TRIANGLE_SIZE * height) + 1;
+        canvas.width = Math.ceil(TRIANGLE_SIZE * (width + 1) / 2) + 1;
+    } else {
+        canvas.height = (CELL_SIZE + 1) * height + 1;
+        canvas.width = (CELL_SIZE + 1) * width + 1;
+    }
+};
+resizeCanvas();
 
 const ctx = canvas.getContext('2d');
 
@@ -62,7 +91,71 @@ max of last 100 = ${Math.round(max)}
     }
 };
 
+// The outline of the hexagon of a cell, pointy side up.
+const hexPath = (row, col, radius) => {
+    const x = universe.hex_center_x(row, col, HEX_SIZE);
+    const y = universe.hex_center_y(row, HEX_SIZE);
+
+    const path = new Path2D();
+    for (let i = 0; i < 6; ++i) {
+        const angle = Math.PI / 3 * i + Math.PI / 6;
+        path.lineTo(x + radius * Math.cos(angle), y + radius * Math.sin(angle));
+    }
+    path.closePath();
+    return path;
+};
+
+// The outline of the triangle of a cell.
+const trianglePath = (row, col) => {
+    const corners = universe.triangle_corners(row, col, TRIANGLE_SIZE);
+
+    const path = new Path2D();
+    path.moveTo(corners[0], corners[1]);
+    path.lineTo(corners[2], corners[3]);
+    path.lineTo(corners[4], corners[5]);
+    path.closePath();
+    return path;
+};
+
+// Draw the outline of every hexagon or triangle.
+const drawCellOutlines = () => {
+    ctx.strokeStyle = GRID_COLOR;
+    for (let row = 0; row < height; ++row) {
+        for (let col = 0; col < width; ++col) {
+            if (grid === "hexagonal") {
+                ctx.stroke(hexPath(row, col, HEX_SIZE));
+            } else {
+                ctx.stroke(trianglePath(row, col));
+            }
+        }
+    }
+};
+
+// Fill a cell with the current fill style.
+const fillCell = (row, col) => {
+    if (grid === "hexagonal") {
+        ctx.fill(hexPath(row, col, HEX_SIZE - 1));
+        return;
+    }
+    if (grid === "triangular") {
+        ctx.fill(trianglePath(row, col));
+        return;
+    }
+
+    ctx.fillRect(
+        col * (CELL_SIZE + 1) + 1,
+        row * (CELL_SIZE + 1) + 1,
+        CELL_SIZE,
+        CELL_SIZE
+    );
+};
+
 const drawGrid = () => {
+    if (grid !== "square") {
+        drawCellOutlines();
+        return;
+    }
+
     ctx.beginPath();
     ctx.strokeStyle = GRID_COLOR;
 
@@ -91,14 +184,64 @@ const bitIsSet = (n, arr) => {
     return (arr[byte] & mask) === mask;
 }
 
+// Refractory states of Generations rules fade from red towards the dead color.
+const stateColor = (state, numStates) => {
+    if (state === Cell.Dead) {
+        return DEAD_COLOR;
+    }
+    if (state === Cell.Alive) {
+        return ALIVE_COLOR;
+    }
+
+    const lightness = 40 + Math.round(50 * (state - 1) / (numStates - 1));
+    return `hsl(0, 80%, ${lightness}%)`;
+};
+
+const drawCellStates = (numStates) => {
+    const statesPtr = universe.cell_states();
+    const states = new Uint8Array(memory.buffer, statesPtr, width * height);
+
+    ctx.beginPath();
+
+    // Group the cells by state so that the fill style only changes once
+    // per state.
+    for (let state = 0; state < numStates; ++state) {
+        ctx.fillStyle = stateColor(state, numStates);
+        for (let row = 0; row < height; ++row) {
+            for (let col = 0; col < width; ++col) {
+                const idx = getIndex(row, col);
+                if (states[idx] !== state) {
+                    continue;
+                }
+
+                fillCell(row, col);
+            }
+        }
+    }
+
+    ctx.stroke();
+};
+
 const drawCells = () => {
+    const numStates = universe.num_states();
+    if (numStates > 2) {
+        drawCellStates(numStates);
+        return;
+    }
+
     const cellsPtr = universe.cells();
     const cells = new Uint8Array(memory.buffer, cellsPtr, width * height / 8);
 
+    // Under rules with B0, set bits are cells that differ from the
+    // background, which is alive on some generations.
+    const background = universe.background();
+    const setColor = background ? DEAD_COLOR : ALIVE_COLOR;
+    const unsetColor = background ? ALIVE_COLOR : DEAD_COLOR;
+
     ctx.beginPath();
 
-    // Alive cells.
-    ctx.fillStyle = ALIVE_COLOR;
+    // Cells that differ from the background.
+    ctx.fillStyle = setColor;
     for (let row = 0; row < height; ++row) {
         for (let col = 0; col < width; ++col) {
             const idx = getIndex(row, col);
@@ -107,17 +250,12 @@ const drawCells = () => {
                 continue;
             }
 
-            ctx.fillRect(
-                col * (CELL_SIZE + 1) + 1,
-                row * (CELL_SIZE + 1) + 1,
-                CELL_SIZE,
-                CELL_SIZE
-            );
+            fillCell(row, col);
         }
     }
 
-    // Dead cells.
-    ctx.fillStyle = DEAD_COLOR;
+    // Cells that match the background.
+    ctx.fillStyle = unsetColor;
     for (let row = 0; row < height; ++row) {
         for (let col = 0; col < width; ++col) {
             const idx = getIndex(row, col);
@@ -126,12 +264,7 @@ const drawCells = () => {
                 continue;
             }
 
-            ctx.fillRect(
-                col * (CELL_SIZE + 1) + 1,
-                row * (CELL_SIZE + 1) + 1,
-                CELL_SIZE,
-                CELL_SIZE
-            );
+            fillCell(row, col);
         }
     } 
 
@@ -148,9 +281,15 @@ const renderLoop = () => {
     drawCells();
 
     // debugger;
-    for (let i = 1; i <= ticksPerFrame.valueAsNumber; ++i)
-    {
-        universe.tick();
+    try {
+        for (let i = 1; i <= ticksPerFrame.valueAsNumber; ++i)
+        {
+            universe.tick();
+        }
+    } catch (e) {
+        pause();
+        reportError("Cannot advance the universe", e);
+        return;
     }
 
     animationId = requestAnimationFrame(renderLoop);
@@ -181,10 +320,42 @@ playPauseButton.addEventListener("click", event => {
     }
 });
 
+// Tell why an action failed. Errors from the universe carry a `code`, such
+// as `OutOfBounds` or `InvalidRule`, along with their message.
+const reportError = (action, e) => {
+    alert(e.code ? `${action} (${e.code}): ${e.message}` : `${action}: ${e}`);
+};
+
 const resetRandomButton = document.getElementById("reset-random");
 
+const seedInput = document.getElementById("seed");
+const symmetrySelect = document.getElementById("symmetry");
+const replaySeedButton = document.getElementById("replay-seed");
+
+seedInput.value = universe.seed();
+
+// Fill the universe with a soup from a seed, or a new one without it.
+const resetWithSeed = seed => {
+    try {
+        seedInput.value = universe.reset_with(seed, 0.5, undefined, symmetrySelect.value);
+    } catch (e) {
+        reportError("Invalid soup", e);
+    }
+
+    drawGrid();
+    drawCells();
+};
+
 resetRandomButton.addEventListener("click", event => {
-    universe.reset();
+    resetWithSeed(undefined);
+});
+
+replaySeedButton.addEventListener("click", event => {
+    try {
+        resetWithSeed(BigInt(seedInput.value));
+    } catch (e) {
+        alert(`Invalid seed: ${seedInput.value}`);
+    }
 });
 
 const resetAllDeadButton = document.getElementById("reset-all-dead");
@@ -193,6 +364,28 @@ resetAllDeadButton.addEventListener("click", event => {
     universe.reset_all_dead();
 });
 
+const ruleInput = document.getElementById("rule");
+const setRuleButton = document.getElementById("set-rule");
+
+ruleInput.value = universe.rule();
+
+setRuleButton.addEventListener("click", event => {
+    try {
+        universe.set_rule(ruleInput.value);
+    } catch (e) {
+        reportError("Invalid rule", e);
+    }
+
+    ruleInput.value = universe.rule();
+
+    if (grid !== gridOf()) {
+        grid = gridOf();
+        resizeCanvas();
+        drawGrid();
+        drawCells();
+    }
+});
+
 canvas.addEventListener("click", event => {
     const boundingRect = canvas.getBoundingClientRect();
 
@@ -202,15 +395,31 @@ canvas.addEventListener("click", event => {
     const canvasLeft = (event.clientX - boundingRect.left) * scaleX;
     const canvasTop = (event.clientY - boundingRect.top) * scaleY;
 
-    const row = Math.min(Math.floor(canvasTop / (CELL_SIZE + 1)), height - 1);
-    const col = Math.min(Math.floor(canvasLeft / (CELL_SIZE + 1)), width - 1);
+    let row = Math.min(Math.floor(canvasTop / (CELL_SIZE + 1)), height - 1);
+    let col = Math.min(Math.floor(canvasLeft / (CELL_SIZE + 1)), width - 1);
 
-    if (event.ctrlKey || event.metaKey) {
-        universe.insert_glider(row, col);
-    } else if (event.shiftKey) {
-        universe.insert_pulsar(row, col);
-    } else {
-        universe.toggle_cell(row, col);
+    if (grid !== "square") {
+        const idx = grid === "hexagonal"
+            ? universe.hex_cell_at(canvasLeft, canvasTop, HEX_SIZE)
+            : universe.triangle_cell_at(canvasLeft, canvasTop, TRIANGLE_SIZE);
+        if (idx === undefined) {
+            return;
+        }
+
+        row = Math.floor(idx / width);
+        col = idx % width;
+    }
+
+    try {
+        if (event.ctrlKey || event.metaKey) {
+            universe.insert_glider(row, col);
+        } else if (event.shiftKey) {
+            universe.insert_pulsar(row, col);
+        } else {
+            universe.toggle_cell(row, col);
+        }
+    } catch (e) {
+        reportError("Cannot edit the universe", e);
     }
 
     drawGrid();